@@ -30,68 +30,17 @@ func setup():
 	
 	Tour.theater_rect.theme = TheaterTheme
 	Tour.theater_rect.confine_input = true
+	# Keep the toggle & reset buttons clickable while input is confined.
+	var allowed_controls: Array[Control] = [toggle_button, reset_button]
+	Tour.theater_rect.allowed_controls = allowed_controls
 	
-	#region Focused Targets
-	
-	var editor_interface = get_editor_interface()
-	
-	var base_control = Tour.get_base_control()
-	
-	var title_bar = Tour.get_title_bar(base_control)
-	var run_bar = Tour.get_run_bar(title_bar)
-	
-	var main_control = Tour.get_main(base_control)
-	var scene_tree_dock = Tour.get_scene_tree_dock(main_control)
-	var scene_tree = scene_tree_dock.find_child("*SceneTreeEditor*", false, false)
-	
-	var main_screen = editor_interface.get_editor_main_screen()
-	
-	#endregion	
-	#region Toggle Button
-	
-	var toggle_button_overlay = Panel.new()
-	toggle_button_overlay.mouse_filter = Control.MOUSE_FILTER_IGNORE
-	Tour.theater_rect.add_child(toggle_button_overlay)
-	
-	var toggle_button_focused_node = Tour.create_focused_node(toggle_button, toggle_button_overlay)
-	Tour.add_focused_node(toggle_button_focused_node)
-	
-	#endregion
-	#region Run Bar
-	
-	var run_bar_overlay = Panel.new()
-	run_bar_overlay.mouse_filter = Control.MOUSE_FILTER_IGNORE
-	Tour.theater_rect.add_child(run_bar_overlay)
-
-	var run_bar_focused_node = Tour.create_focused_node(run_bar, run_bar_overlay)
-	Tour.add_focused_node(run_bar_focused_node)
-	
-	#endregion
-	#region Scene Tree
-	
-	var scene_tree_overlay = Panel.new()
-	scene_tree_overlay.mouse_filter = Control.MOUSE_FILTER_IGNORE
-	Tour.theater_rect.add_child(scene_tree_overlay)
-	
-	var scene_tree_focused_node = Tour.create_focused_node(scene_tree, scene_tree_overlay)
-	Tour.add_focused_node(scene_tree_focused_node)
-	
-	#endregion
-	#region Main Screen
-	
-	var main_screen_overlay = Panel.new()
-	main_screen_overlay.mouse_filter = Control.MOUSE_FILTER_IGNORE
-	Tour.theater_rect.add_child(main_screen_overlay)
-	
-	var main_screen_focused_node = Tour.create_focused_node(main_screen, main_screen_overlay)
-	Tour.add_focused_node(main_screen_focused_node)
-	
-	#endregion
+	if Tour.load_tour("res://addons/custom_tour/tour.json"):
+		Tour.reset_progress(Tour.tour_id)
+		Tour.start()
 
 func clean():
+	Tour.cancel()
 	Tour.clear_focused_nodes()
-	for child in Tour.theater_rect.get_children():
-		child.free()
 
 func on_toggle_pressed():
 	Tour.theater_rect.visible = !Tour.theater_rect.visible
@@ -1,5 +1,14 @@
 pub mod theater_rect;
+pub mod cutout_transition;
+pub mod target_rect;
 pub mod popup_container;
+pub mod selector;
 pub mod focused_node;
+pub mod tour_step;
+pub mod tour_loader;
+pub mod tour_progress;
+pub mod editor_locator;
+pub mod control_picker;
+pub mod editor_tree_panel;
 pub mod tour_plugin;
 pub mod tour_singleton;
@@ -0,0 +1,141 @@
+use godot::prelude::*;
+use godot::classes::{Control, IControl, InputEvent, InputEventKey, InputEventMouseButton, InputEventMouseMotion, Viewport};
+use godot::classes::control::{LayoutPreset, MouseFilter};
+use godot::global::{Key, MouseButton};
+
+use super::target_rect;
+use super::theater_rect::TheaterRect;
+
+/// Overlay for picking a control below `root` with the mouse.
+/// Outlines the hovered control, click to pick it. Right click or escape stops picking.
+#[derive(GodotClass)]
+#[class(tool, init, base=Control)]
+pub struct ControlPicker {
+    base: Base<Control>,
+    /// Control whose descendants can be picked.
+    #[var]
+    pub root: Option<Gd<Control>>,
+    /// Color of the outline around the hovered control.
+    #[export]
+    #[init(val = Color::from_rgba(0.28, 0.55, 0.75, 1.0))]
+    pub outline_color: Color,
+    hovered: Option<Gd<Control>>,
+}
+
+#[godot_api]
+impl IControl for ControlPicker {
+    fn ready(&mut self) {
+        self.base_mut().set_anchors_and_offsets_preset(LayoutPreset::FULL_RECT);
+        self.base_mut().set_mouse_filter(MouseFilter::STOP);
+        self.base_mut().set_visible(false);
+    }
+
+    fn gui_input(&mut self, event: Gd<InputEvent>) {
+        if event.clone().try_cast::<InputEventMouseMotion>().is_ok() {
+            let mouse_position = self.base().get_global_mouse_position();
+            self.hovered = self.get_control_at(mouse_position);
+            self.base_mut().queue_redraw();
+        } else if let Ok(mouse_button) = event.try_cast::<InputEventMouseButton>() {
+            if !mouse_button.is_pressed() {
+                return;
+            }
+            match mouse_button.get_button_index() {
+                MouseButton::LEFT => {
+                    let mouse_position = self.base().get_global_mouse_position();
+                    let picked = self.get_control_at(mouse_position);
+                    self.stop();
+                    if let Some(picked) = picked {
+                        self.base_mut().emit_signal("control_picked", &[picked.to_variant()]);
+                    }
+                }
+                MouseButton::RIGHT => self.stop(),
+                _ => return,
+            }
+        }
+        self.base_mut().accept_event();
+    }
+
+    fn input(&mut self, event: Gd<InputEvent>) {
+        if !self.base().is_visible_in_tree() {
+            return;
+        }
+        let Ok(key) = event.try_cast::<InputEventKey>() else {
+            return;
+        };
+        if key.is_pressed() && key.get_keycode() == Key::ESCAPE {
+            self.stop();
+            if let Some(mut viewport) = self.base().get_viewport() {
+                viewport.set_input_as_handled();
+            }
+        }
+    }
+
+    fn draw(&mut self) {
+        let Some(hovered) = self.hovered.clone().filter(|hovered| hovered.is_instance_valid()) else {
+            return;
+        };
+        let transform = self.base().get_global_transform().affine_inverse();
+        let rect = target_rect::transform_rect(transform, hovered.get_global_rect());
+        let color = self.outline_color;
+        self.base_mut().draw_rect(rect, Color { a: color.a * 0.2, ..color });
+        self.base_mut().draw_rect_ex(rect, color).filled(false).width(2.0).done();
+    }
+}
+
+#[godot_api]
+impl ControlPicker {
+    /// Emitted when a control was clicked.
+    #[signal]
+    fn control_picked(control: Gd<Control>);
+
+    /// Emitted when picking stopped, after a control was picked or picking was cancelled.
+    #[signal]
+    fn pick_stopped();
+
+    #[func]
+    pub fn start(&mut self) {
+        self.hovered = None;
+        self.base_mut().move_to_front();
+        self.base_mut().set_visible(true);
+        self.base_mut().queue_redraw();
+    }
+
+    #[func]
+    pub fn stop(&mut self) {
+        if !self.base().is_visible() {
+            return;
+        }
+        self.hovered = None;
+        self.base_mut().set_visible(false);
+        self.base_mut().emit_signal("pick_stopped", &[]);
+    }
+
+    /// Topmost visible control below `root` containing `point`.
+    fn get_control_at(&self, point: Vector2) -> Option<Gd<Control>> {
+        let root = self.root.clone()?;
+        let mut found = None;
+        self.find_control_at(&root.upcast(), point, &mut found);
+        found
+    }
+
+    // Controls later in the tree are drawn on top, so the last match wins.
+    fn find_control_at(&self, node: &Gd<Node>, point: Vector2, found: &mut Option<Gd<Control>>) {
+        let children = node.get_children_ex().include_internal(true).done();
+        for child in children.iter_shared() {
+            if child.instance_id() == self.base().instance_id()
+                || child.clone().try_cast::<TheaterRect>().is_ok()
+                || child.clone().try_cast::<Viewport>().is_ok() {
+                continue;
+            }
+            if !target_rect::is_target_visible(&child) {
+                continue;
+            }
+            if let Ok(control) = child.clone().try_cast::<Control>() {
+                if control.get_global_rect().contains_point(point) {
+                    *found = Some(control);
+                }
+            }
+            self.find_control_at(&child, point, found);
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use godot::prelude::*;
+
+use super::focused_node::CutoutShape;
+
+/// A single cutout of the TheaterRect.
+#[derive(Clone, Copy, Default, PartialEq, Debug)]
+pub struct Cutout {
+    pub rect: Rect2,
+    /// Radii of the top left, top right, bottom right & bottom left corners.
+    pub corner_radii: Vector4,
+    /// Either `RoundedRect` or `Ellipse`, other shapes are converted by `Cutout::new`.
+    pub shape: CutoutShape,
+}
+
+impl Cutout {
+    /// Cutout of `shape` fit to `rect`. Circles & capsules become rounded rects.
+    pub fn new(rect: Rect2, corner_radii: Vector4, shape: CutoutShape) -> Cutout {
+        match shape {
+            CutoutShape::Circle => {
+                let diameter = rect.size.x.max(rect.size.y);
+                Cutout {
+                    rect: Rect2::new(rect.center() - Vector2::splat(diameter / 2.0), Vector2::splat(diameter)),
+                    corner_radii: Vector4::splat(diameter / 2.0),
+                    shape: CutoutShape::RoundedRect,
+                }
+            }
+            CutoutShape::Capsule => Cutout {
+                rect,
+                corner_radii: Vector4::splat(rect.size.x.min(rect.size.y) / 2.0),
+                shape: CutoutShape::RoundedRect,
+            },
+            CutoutShape::RoundedRect | CutoutShape::Ellipse => Cutout {
+                rect,
+                corner_radii,
+                shape,
+            },
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rect.size.x <= 0.0 || self.rect.size.y <= 0.0
+    }
+
+    /// Zero sized cutout at the center of this one.
+    pub fn collapsed(&self) -> Cutout {
+        Cutout {
+            rect: Rect2::new(self.rect.center(), Vector2::ZERO),
+            corner_radii: Vector4::ZERO,
+            shape: self.shape,
+        }
+    }
+
+    pub fn lerp(&self, to: &Cutout, weight: f32) -> Cutout {
+        Cutout {
+            rect: Rect2::new(
+                self.rect.position.lerp(to.rect.position, weight),
+                self.rect.size.lerp(to.rect.size, weight),
+            ),
+            corner_radii: self.corner_radii + (to.corner_radii - self.corner_radii) * weight,
+            shape: to.shape,
+        }
+    }
+
+    /// Signed distance from the outline, negative inside. Matches `cutout.gdshader`.
+    pub fn distance_to(&self, point: Vector2) -> f32 {
+        let half_size = self.rect.size / 2.0;
+        let local = point - self.rect.center();
+        if self.shape == CutoutShape::Ellipse {
+            let k0 = (local / half_size).length();
+            let k1 = (local / (half_size * half_size)).length();
+            if k0 < 1e-5 {
+                return -half_size.x.min(half_size.y);
+            }
+            return k0 * (k0 - 1.0) / k1;
+        }
+        let radius = match (local.x > 0.0, local.y > 0.0) {
+            (false, false) => self.corner_radii.x,
+            (true, false) => self.corner_radii.y,
+            (true, true) => self.corner_radii.z,
+            (false, true) => self.corner_radii.w,
+        }.min(half_size.x).min(half_size.y);
+        let q = local.abs() - half_size + Vector2::splat(radius);
+        q.x.max(q.y).min(0.0) + Vector2::new(q.x.max(0.0), q.y.max(0.0)).length() - radius
+    }
+
+    pub fn contains_point(&self, point: Vector2) -> bool {
+        !self.is_empty() && self.distance_to(point) <= 0.0
+    }
+}
+
+/// Moves the cutouts of one layout to the cutouts of the next.
+pub struct CutoutTransition {
+    // Start of each cutout of the new layout.
+    from: Vec<Cutout>,
+    // Cutouts of the old layout without a match. These shrink away.
+    removed: Vec<Cutout>,
+    elapsed: f64,
+    duration: f64,
+}
+
+impl CutoutTransition {
+    /// Match every cutout of `to` with the closest unmatched cutout of `displayed`.
+    /// Cutouts without a match grow from their center instead.
+    pub fn new(displayed: &[Cutout], to: &[Cutout], duration: f64) -> Self {
+        let mut unmatched: Vec<Cutout> = displayed.iter().copied().filter(|cutout| !cutout.is_empty()).collect();
+        let from = to.iter().map(|cutout| {
+            if cutout.is_empty() {
+                return *cutout;
+            }
+            let center = cutout.rect.center();
+            let closest = unmatched.iter().enumerate().min_by(|(_, a), (_, b)| {
+                let a_distance = a.rect.center().distance_squared_to(center);
+                let b_distance = b.rect.center().distance_squared_to(center);
+                a_distance.partial_cmp(&b_distance).unwrap_or(std::cmp::Ordering::Equal)
+            }).map(|(index, _)| index);
+            match closest {
+                Some(index) => unmatched.swap_remove(index),
+                None => cutout.collapsed(),
+            }
+        }).collect();
+        Self {
+            from,
+            removed: unmatched,
+            elapsed: 0.0,
+            duration,
+        }
+    }
+
+    pub fn advance(&mut self, delta: f64) {
+        self.elapsed += delta;
+    }
+
+    /// Linear progress between 0 and 1.
+    pub fn progress(&self) -> f64 {
+        if self.duration <= 0.0 {
+            return 1.0;
+        }
+        (self.elapsed / self.duration).clamp(0.0, 1.0)
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.progress() >= 1.0
+    }
+
+    /// Cutouts of the new layout and shrinking cutouts of the old layout at `weight`.
+    pub fn interpolate(&self, to: &[Cutout], weight: f32) -> (Vec<Cutout>, Vec<Cutout>) {
+        let cutouts = to.iter().enumerate().map(|(index, cutout)| {
+            match self.from.get(index) {
+                Some(from) if !cutout.is_empty() => from.lerp(cutout, weight),
+                _ => *cutout,
+            }
+        }).collect();
+        let removed = self.removed.iter().map(|cutout| cutout.lerp(&cutout.collapsed(), weight)).collect();
+        (cutouts, removed)
+    }
+}
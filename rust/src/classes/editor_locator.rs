@@ -0,0 +1,290 @@
+use godot::prelude::*;
+use godot::classes::{Control, Engine};
+
+/// A step from one node to another.
+pub enum LocatorStep {
+    /// First descendant, or child when not `recursive`, inheriting `class` with a name matching `pattern`.
+    Find { class: &'static str, pattern: &'static str, recursive: bool },
+    /// Child at index.
+    Child(i32),
+    /// Next sibling.
+    NextSibling,
+    /// Parent.
+    Parent,
+}
+
+/// Steps leading to a control, used on the Godot versions between `since` & `until` (inclusive).
+pub struct LocatorStrategy {
+    pub steps: &'static [LocatorStep],
+    pub since: Option<(i64, i64)>,
+    pub until: Option<(i64, i64)>,
+}
+
+impl LocatorStrategy {
+    const fn any_version(steps: &'static [LocatorStep]) -> Self {
+        Self { steps, since: None, until: None }
+    }
+
+    const fn since(steps: &'static [LocatorStep], major: i64, minor: i64) -> Self {
+        Self { steps, since: Some((major, minor)), until: None }
+    }
+}
+
+/// Finds an editor control by trying strategies in order, from class based lookups
+/// to structural fallbacks for older editor layouts.
+pub struct EditorLocator {
+    pub name: &'static str,
+    pub strategies: &'static [LocatorStrategy],
+}
+
+impl EditorLocator {
+    pub const TITLE_BAR: EditorLocator = EditorLocator {
+        name: "title_bar",
+        strategies: &[
+            LocatorStrategy::since(&[LocatorStep::Find { class: "EditorTitleBar", pattern: "*", recursive: true }], 4, 2),
+            LocatorStrategy::any_version(&[LocatorStep::Child(0), LocatorStep::Child(0)]),
+        ],
+    };
+
+    /// Control holding everything below the title bar.
+    pub const MAIN: EditorLocator = EditorLocator {
+        name: "main",
+        strategies: &[
+            LocatorStrategy::since(&[
+                LocatorStep::Find { class: "EditorTitleBar", pattern: "*", recursive: true },
+                LocatorStep::NextSibling,
+            ], 4, 2),
+            LocatorStrategy::any_version(&[LocatorStep::Child(0), LocatorStep::Child(1)]),
+        ],
+    };
+
+    /// Play, pause & stop buttons. Only located on Godot 4.2+, older editors have no `EditorRunBar`
+    /// & keep the run buttons in an unnamed container of the title bar.
+    pub const RUN_BAR: EditorLocator = EditorLocator {
+        name: "run_bar",
+        strategies: &[
+            LocatorStrategy::since(&[LocatorStep::Find { class: "EditorRunBar", pattern: "*", recursive: true }], 4, 2),
+        ],
+    };
+
+    pub const SCENE_TREE_DOCK: EditorLocator = EditorLocator {
+        name: "scene_tree_dock",
+        strategies: &[
+            LocatorStrategy::any_version(&[LocatorStep::Find { class: "SceneTreeDock", pattern: "Scene", recursive: true }]),
+            LocatorStrategy::any_version(&[LocatorStep::Find { class: "SceneTreeDock", pattern: "*", recursive: true }]),
+        ],
+    };
+
+    pub const SCENE_TREE: EditorLocator = EditorLocator {
+        name: "scene_tree",
+        strategies: &[
+            LocatorStrategy::any_version(&[
+                LocatorStep::Find { class: "SceneTreeDock", pattern: "*", recursive: true },
+                LocatorStep::Find { class: "SceneTreeEditor", pattern: "*", recursive: false },
+            ]),
+        ],
+    };
+
+    pub const INSPECTOR: EditorLocator = EditorLocator {
+        name: "inspector",
+        strategies: &[
+            LocatorStrategy::any_version(&[LocatorStep::Find { class: "InspectorDock", pattern: "*", recursive: true }]),
+            LocatorStrategy::any_version(&[
+                LocatorStep::Find { class: "EditorInspector", pattern: "*", recursive: true },
+                LocatorStep::Parent,
+            ]),
+        ],
+    };
+
+    /// Signals & groups dock.
+    pub const NODE_DOCK: EditorLocator = EditorLocator {
+        name: "node_dock",
+        strategies: &[
+            LocatorStrategy::any_version(&[LocatorStep::Find { class: "NodeDock", pattern: "*", recursive: true }]),
+            LocatorStrategy::any_version(&[LocatorStep::Find { class: "ConnectionsDock", pattern: "*", recursive: true }]),
+        ],
+    };
+
+    pub const FILESYSTEM_DOCK: EditorLocator = EditorLocator {
+        name: "filesystem_dock",
+        strategies: &[
+            LocatorStrategy::any_version(&[LocatorStep::Find { class: "FileSystemDock", pattern: "*", recursive: true }]),
+        ],
+    };
+
+    pub const IMPORT_DOCK: EditorLocator = EditorLocator {
+        name: "import_dock",
+        strategies: &[
+            LocatorStrategy::any_version(&[LocatorStep::Find { class: "ImportDock", pattern: "*", recursive: true }]),
+        ],
+    };
+
+    pub const HISTORY_DOCK: EditorLocator = EditorLocator {
+        name: "history_dock",
+        strategies: &[
+            LocatorStrategy::any_version(&[LocatorStep::Find { class: "HistoryDock", pattern: "*", recursive: true }]),
+        ],
+    };
+
+    /// Output bottom panel.
+    pub const OUTPUT_PANEL: EditorLocator = EditorLocator {
+        name: "output_panel",
+        strategies: &[
+            LocatorStrategy::any_version(&[LocatorStep::Find { class: "EditorLog", pattern: "*", recursive: true }]),
+        ],
+    };
+
+    /// Debugger bottom panel.
+    pub const DEBUGGER_PANEL: EditorLocator = EditorLocator {
+        name: "debugger_panel",
+        strategies: &[
+            LocatorStrategy::any_version(&[LocatorStep::Find { class: "EditorDebuggerNode", pattern: "*", recursive: true }]),
+        ],
+    };
+
+    /// Animation bottom panel.
+    pub const ANIMATION_PANEL: EditorLocator = EditorLocator {
+        name: "animation_panel",
+        strategies: &[
+            LocatorStrategy::any_version(&[LocatorStep::Find { class: "AnimationPlayerEditor", pattern: "*", recursive: true }]),
+        ],
+    };
+
+    /// Shader Editor bottom panel.
+    pub const SHADER_EDITOR_PANEL: EditorLocator = EditorLocator {
+        name: "shader_editor_panel",
+        strategies: &[
+            LocatorStrategy::any_version(&[LocatorStep::Find { class: "WindowWrapper", pattern: "*Shader*", recursive: true }]),
+        ],
+    };
+
+    /// Container of the 2D, 3D, Script & AssetLib buttons. Searched in the title bar only, other docks & plugins
+    /// have buttons with the same names.
+    pub const MAIN_SCREEN_BUTTONS: EditorLocator = EditorLocator {
+        name: "main_screen_buttons",
+        strategies: &[
+            LocatorStrategy::since(&[
+                LocatorStep::Find { class: "EditorTitleBar", pattern: "*", recursive: true },
+                LocatorStep::Find { class: "Button", pattern: "2D", recursive: true },
+                LocatorStep::Parent,
+            ], 4, 2),
+            LocatorStrategy::since(&[
+                LocatorStep::Find { class: "EditorTitleBar", pattern: "*", recursive: true },
+                LocatorStep::Find { class: "Button", pattern: "Script", recursive: true },
+                LocatorStep::Parent,
+            ], 4, 2),
+            LocatorStrategy::any_version(&[
+                LocatorStep::Child(0),
+                LocatorStep::Child(0),
+                LocatorStep::Find { class: "Button", pattern: "2D", recursive: true },
+                LocatorStep::Parent,
+            ]),
+            LocatorStrategy::any_version(&[
+                LocatorStep::Child(0),
+                LocatorStep::Child(0),
+                LocatorStep::Find { class: "Button", pattern: "Script", recursive: true },
+                LocatorStep::Parent,
+            ]),
+        ],
+    };
+
+    /// Tabs of the open scenes.
+    pub const SCENE_TABS: EditorLocator = EditorLocator {
+        name: "scene_tabs",
+        strategies: &[
+            LocatorStrategy::any_version(&[LocatorStep::Find { class: "EditorSceneTabs", pattern: "*", recursive: true }]),
+        ],
+    };
+
+    /// Main menu bar with the Scene, Project, Debug, Editor & Help menus.
+    pub const MENU_BAR: EditorLocator = EditorLocator {
+        name: "menu_bar",
+        strategies: &[
+            LocatorStrategy::since(&[
+                LocatorStep::Find { class: "EditorTitleBar", pattern: "*", recursive: true },
+                LocatorStep::Find { class: "MenuBar", pattern: "*", recursive: true },
+            ], 4, 2),
+            LocatorStrategy::any_version(&[LocatorStep::Find { class: "MenuBar", pattern: "*", recursive: true }]),
+        ],
+    };
+
+    /// Locators available by name, starting at the editor's base control.
+    pub const ALL: &'static [EditorLocator] = &[
+        Self::TITLE_BAR,
+        Self::MAIN,
+        Self::RUN_BAR,
+        Self::SCENE_TREE_DOCK,
+        Self::SCENE_TREE,
+        Self::INSPECTOR,
+        Self::NODE_DOCK,
+        Self::FILESYSTEM_DOCK,
+        Self::IMPORT_DOCK,
+        Self::HISTORY_DOCK,
+        Self::OUTPUT_PANEL,
+        Self::DEBUGGER_PANEL,
+        Self::ANIMATION_PANEL,
+        Self::SHADER_EDITOR_PANEL,
+        Self::MAIN_SCREEN_BUTTONS,
+        Self::SCENE_TABS,
+        Self::MENU_BAR,
+    ];
+
+    pub fn get(name: &str) -> Option<&'static EditorLocator> {
+        Self::ALL.iter().find(|locator| locator.name == name)
+    }
+
+    /// Find the control starting at `root`. Warns and returns None if no strategy matches.
+    pub fn locate(&self, root: &Gd<Control>) -> Option<Gd<Control>> {
+        let version = Self::get_godot_version();
+        let control = self.strategies.iter()
+            .filter(|strategy| strategy.since.is_none_or(|since| version >= since))
+            .filter(|strategy| strategy.until.is_none_or(|until| version <= until))
+            .find_map(|strategy| Self::follow(root.clone().upcast(), strategy.steps));
+        if control.is_none() {
+            godot_warn!("Tour: couldn't locate editor control \"{}\".", self.name);
+        }
+        control
+    }
+
+    fn follow(root: Gd<Node>, steps: &[LocatorStep]) -> Option<Gd<Control>> {
+        let mut node = root;
+        for step in steps {
+            node = match step {
+                LocatorStep::Find { class, pattern, recursive } => {
+                    node.find_children_ex(*pattern)
+                        .type_(*class)
+                        .recursive(*recursive)
+                        .owned(false)
+                        .done()
+                        .get(0)?
+                }
+                LocatorStep::Child(index) => {
+                    if *index >= node.get_child_count() {
+                        return None;
+                    }
+                    node.get_child(*index)?
+                }
+                LocatorStep::NextSibling => {
+                    let parent = node.get_parent()?;
+                    let index = node.get_index() + 1;
+                    if index >= parent.get_child_count() {
+                        return None;
+                    }
+                    parent.get_child(index)?
+                }
+                LocatorStep::Parent => node.get_parent()?,
+            };
+        }
+        node.try_cast::<Control>().ok()
+    }
+
+    fn get_godot_version() -> (i64, i64) {
+        let version_info = Engine::singleton().get_version_info();
+        let get = |key: &str| {
+            version_info.get(GString::from(key).to_variant())
+                .and_then(|value| value.try_to::<i64>().ok())
+                .unwrap_or(0)
+        };
+        (get("major"), get("minor"))
+    }
+}
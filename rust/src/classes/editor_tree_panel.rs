@@ -0,0 +1,529 @@
+use godot::prelude::*;
+use godot::classes::{
+    Button, CheckBox, Control, DisplayServer, HBoxContainer, HSplitContainer, IVBoxContainer, Label, LineEdit, Timer,
+    Tree, TreeItem, VBoxContainer,
+};
+use godot::classes::control::SizeFlags;
+use godot::classes::object::ConnectFlags;
+use godot::obj::EngineEnum;
+
+use super::control_picker::ControlPicker;
+use super::focused_node::FocusedNode;
+use super::selector::Selector;
+use super::tour_plugin::TourPlugin;
+use super::tour_singleton::TourSingleton;
+
+/// "Editor Tree" bottom panel. Lists the controls below `root` & picks controls with the mouse.
+/// Items are created when their parent is expanded, except while filtering.
+#[derive(GodotClass)]
+#[class(tool, init, base=VBoxContainer)]
+pub struct EditorTreePanel {
+    base: Base<VBoxContainer>,
+    /// Control shown at the top of the tree.
+    #[var]
+    pub root: Option<Gd<Control>>,
+    /// Seconds a selected control stays highlighted by the theater_rect.
+    #[export]
+    #[init(val = 1.5)]
+    pub spotlight_duration: f64,
+    tree: Option<Gd<Tree>>,
+    picker: Option<Gd<ControlPicker>>,
+    pick_button: Option<Gd<Button>>,
+    copy_button: Option<Gd<Button>>,
+    details: Option<Gd<Tree>>,
+    copy_path_button: Option<Gd<Button>>,
+    copy_selector_button: Option<Gd<Button>>,
+    selected: Option<Gd<Control>>,
+    search: String,
+    // Rebuilds the tree once typing in the search box paused.
+    search_timer: Option<Gd<Timer>>,
+    visible_only: bool,
+    text_only: bool,
+    // Focused node of the current spotlight & the confine_input of the theater_rect before it.
+    spotlight: Option<(Gd<FocusedNode>, bool)>,
+    spotlight_count: i64,
+}
+
+#[godot_api]
+impl IVBoxContainer for EditorTreePanel {
+    fn ready(&mut self) {
+        let mut toolbar = HBoxContainer::new_alloc();
+
+        let mut update_button = Button::new_alloc();
+        update_button.set_text("Update Tree");
+        update_button.connect("pressed", &self.to_gd().callable("update_tree"));
+        toolbar.add_child(&update_button);
+
+        let mut pick_button = Button::new_alloc();
+        pick_button.set_text("Pick");
+        pick_button.set_tooltip_text("Hover a control to outline it, click to select it. Right click or escape to cancel.");
+        pick_button.set_toggle_mode(true);
+        pick_button.connect("toggled", &self.to_gd().callable("on_pick_toggled"));
+        toolbar.add_child(&pick_button);
+
+        let mut copy_button = Button::new_alloc();
+        copy_button.set_text("Copy as FocusedNode");
+        copy_button.set_tooltip_text("Copy GDScript creating a FocusedNode for the selected control.");
+        copy_button.set_disabled(true);
+        copy_button.connect("pressed", &self.to_gd().callable("copy_as_focused_node"));
+        toolbar.add_child(&copy_button);
+
+        let mut search_edit = LineEdit::new_alloc();
+        search_edit.set_placeholder("Filter by name, class or text");
+        search_edit.set_clear_button_enabled(true);
+        search_edit.set_h_size_flags(SizeFlags::EXPAND_FILL);
+        search_edit.connect("text_changed", &self.to_gd().callable("on_search_changed"));
+        toolbar.add_child(&search_edit);
+
+        let mut search_timer = Timer::new_alloc();
+        search_timer.set_one_shot(true);
+        search_timer.set_wait_time(Self::SEARCH_DELAY);
+        search_timer.connect("timeout", &self.to_gd().callable("update_tree"));
+
+        let mut visible_only_check = CheckBox::new_alloc();
+        visible_only_check.set_text("Visible Only");
+        visible_only_check.connect("toggled", &self.to_gd().callable("on_visible_only_toggled"));
+        toolbar.add_child(&visible_only_check);
+
+        let mut text_only_check = CheckBox::new_alloc();
+        text_only_check.set_text("Text Only");
+        text_only_check.set_tooltip_text("Only show controls with text, like labels & buttons.");
+        text_only_check.connect("toggled", &self.to_gd().callable("on_text_only_toggled"));
+        toolbar.add_child(&text_only_check);
+
+        let mut tree = Tree::new_alloc();
+        tree.set_h_size_flags(SizeFlags::EXPAND_FILL);
+        // Deferred, selecting & expanding items while picking would call back into this panel.
+        tree.connect_ex("item_selected", &self.to_gd().callable("on_item_selected"))
+            .flags(ConnectFlags::DEFERRED.ord() as u32)
+            .done();
+        tree.connect_ex("item_collapsed", &self.to_gd().callable("on_item_collapsed"))
+            .flags(ConnectFlags::DEFERRED.ord() as u32)
+            .done();
+
+        let mut details_container = VBoxContainer::new_alloc();
+        details_container.set_h_size_flags(SizeFlags::EXPAND_FILL);
+
+        let mut details = Tree::new_alloc();
+        details.set_columns(2);
+        details.set_hide_root(true);
+        details.set_column_expand_ratio(1, 2);
+        details.set_v_size_flags(SizeFlags::EXPAND_FILL);
+        details_container.add_child(&details);
+
+        let mut details_buttons = HBoxContainer::new_alloc();
+        let mut copy_path_button = Button::new_alloc();
+        copy_path_button.set_text("Copy Path");
+        copy_path_button.set_disabled(true);
+        copy_path_button.connect("pressed", &self.to_gd().callable("copy_path"));
+        details_buttons.add_child(&copy_path_button);
+        let mut copy_selector_button = Button::new_alloc();
+        copy_selector_button.set_text("Copy Selector");
+        copy_selector_button.set_disabled(true);
+        copy_selector_button.connect("pressed", &self.to_gd().callable("copy_selector"));
+        details_buttons.add_child(&copy_selector_button);
+        details_container.add_child(&details_buttons);
+
+        let mut split = HSplitContainer::new_alloc();
+        split.set_v_size_flags(SizeFlags::EXPAND_FILL);
+        split.add_child(&tree);
+        split.add_child(&details_container);
+
+        self.base_mut().add_child(&toolbar);
+        self.base_mut().add_child(&split);
+        self.base_mut().add_child(&search_timer);
+        self.search_timer = Some(search_timer);
+        self.pick_button = Some(pick_button);
+        self.copy_button = Some(copy_button);
+        self.details = Some(details);
+        self.copy_path_button = Some(copy_path_button);
+        self.copy_selector_button = Some(copy_selector_button);
+        self.tree = Some(tree);
+        self.update_tree();
+    }
+}
+
+#[godot_api]
+impl EditorTreePanel {
+    // Seconds without typing before the search is applied.
+    const SEARCH_DELAY: f64 = 0.3;
+
+    /// Rebuild the tree from `root`.
+    #[func]
+    pub fn update_tree(&mut self) {
+        let Some(mut tree) = self.tree.clone() else {
+            return;
+        };
+        tree.clear();
+        let Some(control) = self.root.clone() else {
+            return;
+        };
+        if let Some(mut root) = tree.create_item() {
+            root.set_text(0, &format!("{:?} -> {:?} = {:?}", control.get_name(), control.get_class(), control));
+            root.set_metadata(0, &control.to_variant());
+            if self.is_filtering() {
+                for child in self.get_child_controls(&control) {
+                    if let Some(filtered) = self.filter_control(&child) {
+                        self.create_filtered_tree_item(&mut root, &filtered);
+                    }
+                }
+            } else {
+                for child in self.get_child_controls(&control) {
+                    self.create_tree_item(&mut root, &child);
+                }
+            }
+        }
+    }
+
+    #[func]
+    fn on_search_changed(&mut self, text: GString) {
+        self.search = text.to_string().to_lowercase();
+        match self.search_timer.clone() {
+            Some(mut search_timer) => search_timer.start(),
+            None => self.update_tree(),
+        }
+    }
+
+    #[func]
+    fn on_visible_only_toggled(&mut self, toggled: bool) {
+        self.visible_only = toggled;
+        self.update_tree();
+    }
+
+    #[func]
+    fn on_text_only_toggled(&mut self, toggled: bool) {
+        self.text_only = toggled;
+        self.update_tree();
+    }
+
+    #[func]
+    fn on_item_collapsed(&mut self, item: Option<Gd<TreeItem>>) {
+        // Items are freed when the tree is rebuilt before the deferred call.
+        if let Some(mut item) = item.filter(|item| item.is_instance_valid() && !item.is_collapsed()) {
+            self.populate_tree_item(&mut item);
+        }
+    }
+
+    #[func]
+    fn on_pick_toggled(&mut self, toggled: bool) {
+        if toggled {
+            self.get_picker().bind_mut().start();
+        } else if let Some(mut picker) = self.picker.clone() {
+            // Allow `pick_stopped` to call back into this panel.
+            let _guard = self.base_mut();
+            picker.bind_mut().stop();
+        }
+    }
+
+    #[func]
+    fn on_pick_stopped(&mut self) {
+        if let Some(mut pick_button) = self.pick_button.clone() {
+            pick_button.set_pressed_no_signal(false);
+        }
+    }
+
+    #[func]
+    fn on_control_picked(&mut self, control: Gd<Control>) {
+        self.set_selected(Some(control.clone()));
+        let item = self.reveal_tree_item(&control).or_else(|| {
+            // Control was added after its parent was expanded.
+            self.update_tree();
+            self.reveal_tree_item(&control)
+        });
+        if let (Some(mut item), Some(mut tree)) = (item, self.tree.clone()) {
+            item.uncollapse_tree();
+            item.select(0);
+            tree.scroll_to_item(&item);
+        }
+    }
+
+    #[func]
+    fn on_item_selected(&mut self) {
+        let control = self.tree.as_ref()
+            .and_then(|tree| tree.get_selected())
+            .and_then(|item| item.get_metadata(0).try_to::<Gd<Control>>().ok());
+        if let Some(control) = &control {
+            self.start_spotlight(control);
+        }
+        self.set_selected(control);
+    }
+
+    /// Copy GDScript creating a focused node for the selected control to the clipboard.
+    #[func]
+    fn copy_as_focused_node(&self) {
+        let Some(selector) = self.selected.as_ref().and_then(Self::get_selector) else {
+            return;
+        };
+        let selector = selector.replace('\\', "\\\\").replace('"', "\\\"");
+        let snippet = format!(
+            "var focused_node := Tour.create_focused_node_by_selector(\"{selector}\", null)\nTour.add_focused_node(focused_node)\n"
+        );
+        DisplayServer::singleton().clipboard_set(&snippet);
+    }
+
+    #[func]
+    fn copy_path(&self) {
+        if let Some(control) = &self.selected {
+            DisplayServer::singleton().clipboard_set(&control.get_path().to_string());
+        }
+    }
+
+    #[func]
+    fn copy_selector(&self) {
+        if let Some(selector) = self.selected.as_ref().and_then(Self::get_selector) {
+            DisplayServer::singleton().clipboard_set(&selector);
+        }
+    }
+
+    /// Remove the spotlight started by the `spotlight_count`-th selection, unless a newer one replaced it.
+    #[func]
+    fn end_spotlight(&mut self, spotlight_count: i64) {
+        if spotlight_count != self.spotlight_count {
+            return;
+        }
+        let Some((focused_node, confine_input)) = self.spotlight.take() else {
+            return;
+        };
+        let mut theater_rect = TourPlugin::get_tour_singleton().bind().theater_rect.clone();
+        let mut theater_rect = theater_rect.bind_mut();
+        theater_rect.confine_input = confine_input;
+        // A tour started in the meantime keeps its focused nodes.
+        if theater_rect.focused_nodes.len() == 1 && theater_rect.focused_nodes.contains(Some(&focused_node)) {
+            theater_rect.focused_nodes.clear();
+        }
+    }
+
+    /// Highlight `control` with the theater_rect for `spotlight_duration` seconds.
+    /// Does nothing while a tour is running.
+    fn start_spotlight(&mut self, control: &Gd<Control>) {
+        let tour = TourPlugin::get_tour_singleton();
+        if tour.bind().is_running() || self.spotlight_duration <= 0.0 {
+            return;
+        }
+        self.end_spotlight(self.spotlight_count);
+        self.spotlight_count += 1;
+
+        let focused_node = TourSingleton::create_focused_node(Some(control.clone().upcast()), None);
+        let mut theater_rect = tour.bind().theater_rect.clone();
+        {
+            let mut theater_rect = theater_rect.bind_mut();
+            self.spotlight = Some((focused_node.clone(), theater_rect.confine_input));
+            // Keep the editor usable while the control is highlighted.
+            theater_rect.confine_input = false;
+            let mut focused_nodes = Array::new();
+            focused_nodes.push(Some(&focused_node));
+            theater_rect.focused_nodes = focused_nodes;
+            theater_rect.show_animated();
+        }
+
+        if let Some(mut timer) = self.base().get_tree().and_then(|mut tree| tree.create_timer(self.spotlight_duration)) {
+            let callable = self.to_gd().callable("end_spotlight").bind(&[self.spotlight_count.to_variant()]);
+            timer.connect("timeout", &callable);
+        }
+    }
+
+    fn get_picker(&mut self) -> Gd<ControlPicker> {
+        if let Some(picker) = self.picker.clone() {
+            return picker;
+        }
+        let mut picker = ControlPicker::new_alloc();
+        picker.bind_mut().root = self.root.clone();
+        picker.connect("control_picked", &self.to_gd().callable("on_control_picked"));
+        picker.connect("pick_stopped", &self.to_gd().callable("on_pick_stopped"));
+        if let Some(mut root) = self.root.clone() {
+            root.add_child(&picker);
+        }
+        self.picker = Some(picker.clone());
+        picker
+    }
+
+    fn set_selected(&mut self, control: Option<Gd<Control>>) {
+        let control = control.filter(|control| control.is_instance_valid());
+        for button in [&self.copy_button, &self.copy_path_button, &self.copy_selector_button] {
+            if let Some(mut button) = button.clone() {
+                button.set_disabled(control.is_none());
+            }
+        }
+        self.update_details(control.as_ref());
+        self.selected = control;
+    }
+
+    fn update_details(&self, control: Option<&Gd<Control>>) {
+        let Some(mut details) = self.details.clone() else {
+            return;
+        };
+        details.clear();
+        let (Some(control), Some(mut root)) = (control, details.create_item()) else {
+            return;
+        };
+        let mut add_row = |name: &str, value: String| {
+            if let Some(mut item) = root.create_child() {
+                item.set_text(0, name);
+                item.set_text(1, &value);
+                item.set_tooltip_text(1, &value);
+            }
+        };
+        let rect = control.get_global_rect();
+        add_row("Class", control.get_class().to_string());
+        add_row("Path", control.get_path().to_string());
+        add_row("Selector", Self::get_selector(control).unwrap_or_default());
+        add_row("Global Rect", format!(
+            "position: ({}, {}), size: ({}, {})",
+            rect.position.x, rect.position.y, rect.size.x, rect.size.y
+        ));
+        add_row("Visible", format!("{} (in tree: {})", control.is_visible(), control.is_visible_in_tree()));
+        add_row("Mouse Filter", control.get_mouse_filter().as_str().to_string());
+        add_row("Focus Mode", control.get_focus_mode().as_str().to_string());
+        add_row("Tooltip", control.get_tooltip_text().to_string());
+        add_row("Theme Type Variation", control.get_theme_type_variation().to_string());
+
+        let Some(mut signals_item) = root.create_child() else {
+            return;
+        };
+        signals_item.set_text(0, "Connected Signals");
+        let mut connection_count = 0;
+        for signal in control.get_signal_list().iter_shared() {
+            let Some(name) = signal.get(GString::from("name").to_variant()).map(|name| name.to_string()) else {
+                continue;
+            };
+            for connection in control.get_signal_connection_list(name.as_str()).iter_shared() {
+                let callable = connection.get(GString::from("callable").to_variant()).map(|callable| callable.to_string()).unwrap_or_default();
+                if let Some(mut item) = signals_item.create_child() {
+                    item.set_text(0, &name);
+                    item.set_text(1, &callable);
+                    item.set_tooltip_text(1, &callable);
+                }
+                connection_count += 1;
+            }
+        }
+        signals_item.set_text(1, &connection_count.to_string());
+        signals_item.set_collapsed(connection_count > 10);
+    }
+
+    /// Selector finding `control` from the root of its tree, as used by `FocusedNode.selector`.
+    fn get_selector(control: &Gd<Control>) -> Option<String> {
+        let root = control.get_tree()?.get_root()?;
+        Selector::generate(&control.clone().upcast(), &root.upcast())
+    }
+
+    /// Item of `control`, creating the items of its ancestors. None if `control` is filtered out.
+    fn reveal_tree_item(&self, control: &Gd<Control>) -> Option<Gd<TreeItem>> {
+        let root = self.root.clone()?.upcast::<Node>();
+        let mut ancestors = Vec::new();
+        let mut node = control.clone().upcast::<Node>();
+        while node != root {
+            ancestors.push(node.clone());
+            node = node.get_parent()?;
+        }
+        let mut item = self.tree.as_ref()?.get_root()?;
+        for ancestor in ancestors.iter().rev() {
+            self.populate_tree_item(&mut item);
+            let mut child = item.get_first_child();
+            item = loop {
+                let current = child?;
+                if current.get_metadata(0).object_id() == Some(ancestor.instance_id()) {
+                    break current;
+                }
+                child = current.get_next();
+            };
+        }
+        Some(item)
+    }
+
+    fn is_filtering(&self) -> bool {
+        !self.search.is_empty() || self.text_only
+    }
+
+    fn matches_filter(&self, control: &Gd<Control>) -> bool {
+        let text = Self::get_control_text(control).unwrap_or_default().to_lowercase();
+        if self.text_only && text.is_empty() {
+            return false;
+        }
+        self.search.is_empty()
+            || control.get_name().to_string().to_lowercase().contains(&self.search)
+            || control.get_class().to_string().to_lowercase().contains(&self.search)
+            || text.contains(&self.search)
+    }
+
+    fn get_child_controls(&self, control: &Gd<Control>) -> Vec<Gd<Control>> {
+        control.get_children_ex().include_internal(true).done()
+            .iter_shared()
+            .filter_map(|child| child.try_cast::<Control>().ok())
+            .filter(|child| !self.visible_only || child.is_visible())
+            .collect()
+    }
+
+    fn get_control_text(control: &Gd<Control>) -> Option<String> {
+        if let Ok(label) = control.clone().try_cast::<Label>() {
+            return Some(label.get_text().to_string());
+        }
+        if let Ok(button) = control.clone().try_cast::<Button>() {
+            return Some(button.get_text().to_string());
+        }
+        if let Ok(line_edit) = control.clone().try_cast::<LineEdit>() {
+            return Some(line_edit.get_text().to_string());
+        }
+        None
+    }
+
+    fn create_tree_item(&self, parent: &mut Gd<TreeItem>, control: &Gd<Control>) -> Option<Gd<TreeItem>> {
+        let mut node_item = parent.create_child()?;
+        node_item.set_collapsed(true);
+        match Self::get_control_text(control) {
+            Some(text) => node_item.set_text(0, &format!("{} -> {} = {}", control.get_name(), control.get_class(), text)),
+            None => node_item.set_text(0, &format!("{} -> {}", control.get_name(), control.get_class())),
+        }
+        node_item.set_metadata(0, &control.to_variant());
+        if !self.is_filtering() && !self.get_child_controls(control).is_empty() {
+            // Placeholder without metadata, replaced by the children when expanded.
+            node_item.create_child();
+        }
+        Some(node_item)
+    }
+
+    /// Replace the placeholder of an item with the items of its children.
+    fn populate_tree_item(&self, item: &mut Gd<TreeItem>) {
+        let Some(placeholder) = item.get_first_child() else {
+            return;
+        };
+        if !placeholder.get_metadata(0).is_nil() {
+            return;
+        }
+        placeholder.free();
+        let Ok(control) = item.get_metadata(0).try_to::<Gd<Control>>() else {
+            return;
+        };
+        for child in self.get_child_controls(&control) {
+            self.create_tree_item(item, &child);
+        }
+    }
+
+    /// `control` with the filtered children below it. None if neither it nor a descendant matches the filter.
+    fn filter_control(&self, control: &Gd<Control>) -> Option<FilteredControl> {
+        let children: Vec<FilteredControl> = self.get_child_controls(control)
+            .iter()
+            .filter_map(|child| self.filter_control(child))
+            .collect();
+        if children.is_empty() && !self.matches_filter(control) {
+            return None;
+        }
+        Some(FilteredControl { control: control.clone(), children })
+    }
+
+    /// Items of a filtered control & its filtered children, expanded.
+    fn create_filtered_tree_item(&self, parent: &mut Gd<TreeItem>, filtered: &FilteredControl) {
+        let Some(mut item) = self.create_tree_item(parent, &filtered.control) else {
+            return;
+        };
+        item.set_collapsed(false);
+        for child in &filtered.children {
+            self.create_filtered_tree_item(&mut item, child);
+        }
+    }
+}
+
+/// A control matching the filter, or an ancestor of one.
+struct FilteredControl {
+    control: Gd<Control>,
+    children: Vec<FilteredControl>,
+}
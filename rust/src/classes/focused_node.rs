@@ -1,20 +1,131 @@
 use godot::prelude::*;
 use godot::builtin::NodePath;
-use godot::classes::Resource;
+use godot::classes::{Engine, Resource};
+
+use super::selector::Selector;
+
+/// Shape of the cutout around a target.
+#[derive(GodotConvert, Var, Export, Default, Clone, Copy, PartialEq, Debug)]
+#[godot(via = i64)]
+pub enum CutoutShape {
+    /// Rect with the corner radii of the overlay's `StyleBoxFlat`.
+    #[default]
+    RoundedRect,
+    /// Ellipse fit inside the rect.
+    Ellipse,
+    /// Circle around the center of the rect.
+    Circle,
+    /// Rect with fully rounded short sides.
+    Capsule,
+}
+
+impl CutoutShape {
+    /// Parse a snake case shape name, e.g. `"rounded_rect"`.
+    pub fn from_name(name: &str) -> Option<CutoutShape> {
+        match name {
+            "rounded_rect" => Some(CutoutShape::RoundedRect),
+            "ellipse" => Some(CutoutShape::Ellipse),
+            "circle" => Some(CutoutShape::Circle),
+            "capsule" => Some(CutoutShape::Capsule),
+            _ => None,
+        }
+    }
+}
 
 #[derive(GodotClass)]
 #[class(tool, init, base=Resource)]
 pub struct FocusedNode {
     base: Base<Resource>,
-    /// Node to focus on.
+    /// Node to focus on. `Node2D`s & `Node3D`s are focused on their bounding rect.
     #[export]
-    #[var(hint = NODE_PATH_VALID_TYPES, hint_string = "Control")]
+    #[var(hint = NODE_PATH_VALID_TYPES, hint_string = "Control,Node2D,Node3D")]
     pub target: NodePath,
+    /// Selector of the target, used instead of `target` when set. Queried from the root of the tree.
+    #[export]
+    pub selector: GString,
+    /// Called without arguments to find the target, used instead of `target` & `selector` when valid.
+    #[var]
+    #[init(val = Callable::invalid())]
+    pub resolver: Callable,
     /// Overlay node for borders and other styles/effects.
     #[export]
     #[var(hint = NODE_PATH_VALID_TYPES, hint_string = "Panel")]
     pub overlay: NodePath,
+    /// Shape of the cutout.
+    #[export]
+    pub shape: CutoutShape,
+    // Last resolved target & what it was resolved from.
+    resolved: Option<(Gd<Node>, ResolveKey)>,
+    // What the last failed lookup was resolved from & its process frame.
+    failed: Option<(ResolveKey, u64)>,
+}
+
+/// Everything a lookup depends on.
+#[derive(Clone, PartialEq)]
+struct ResolveKey {
+    target: NodePath,
+    selector: GString,
+    resolver: Callable,
+    // Only `target` paths depend on the node they're resolved from, selectors & resolvers don't.
+    relative_to: Option<InstanceId>,
 }
 
 #[godot_api]
 impl FocusedNode {}
+
+impl FocusedNode {
+    // Frames to wait before looking up a target again that couldn't be found.
+    const RETRY_FRAMES: u64 = 30;
+
+    /// Target of this focused node. `target` paths are resolved relative to `relative_to`.
+    /// The target is cached until it's freed, leaves the tree or `target`, `selector`, `resolver` or, for
+    /// `target` paths, `relative_to` change. Failed lookups are only retried every few frames.
+    pub fn resolve(&mut self, relative_to: &Gd<Node>) -> Option<Gd<Node>> {
+        let key = ResolveKey {
+            target: self.target.clone(),
+            selector: self.selector.clone(),
+            resolver: self.resolver.clone(),
+            relative_to: self.uses_relative_to().then(|| relative_to.instance_id()),
+        };
+        if let Some((node, resolved_key)) = &self.resolved {
+            if *resolved_key == key && node.is_instance_valid() && node.is_inside_tree() {
+                return Some(node.clone());
+            }
+        }
+        self.resolved = None;
+        let frame = Engine::singleton().get_process_frames();
+        if let Some((failed_key, failed_frame)) = &self.failed {
+            if *failed_key == key && frame < failed_frame + Self::RETRY_FRAMES {
+                return None;
+            }
+        }
+        let Some(node) = self.find_target(relative_to) else {
+            self.failed = Some((key, frame));
+            return None;
+        };
+        self.failed = None;
+        self.resolved = Some((node.clone(), key));
+        Some(node)
+    }
+
+    /// Whether the target is looked up from `relative_to` instead of the root of the tree.
+    fn uses_relative_to(&self) -> bool {
+        !self.resolver.is_valid() && self.selector.is_empty()
+    }
+
+    fn find_target(&mut self, relative_to: &Gd<Node>) -> Option<Gd<Node>> {
+        if self.resolver.is_valid() {
+            let resolver = self.resolver.clone();
+            // Allow the resolver to access this focused node.
+            let _guard = self.base_mut();
+            return resolver.call(&[]).try_to::<Option<Gd<Node>>>().ok().flatten();
+        }
+        if !self.selector.is_empty() {
+            // Invalid selectors match nothing.
+            let selector = Selector::parse(&self.selector.to_string()).ok()?;
+            let root = relative_to.get_tree()?.get_root()?;
+            return selector.query(&root.upcast());
+        }
+        relative_to.try_get_node_as::<Node>(&self.target)
+    }
+}
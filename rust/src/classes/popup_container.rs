@@ -1,11 +1,14 @@
-use std::cmp::Ordering;
-
 use godot::prelude::*;
-use godot::classes::{control, notify, Container, Control, IContainer};
+use godot::builtin::{Corner, Side};
+use godot::classes::{control, notify, Container, Control, IContainer, StyleBoxFlat};
+
+use super::focused_node::FocusedNode;
+use super::target_rect;
+use super::theater_rect::TheaterRect;
 
-#[derive(GodotConvert, Var, Export, Default, Clone)]
+#[derive(GodotConvert, Var, Export, Default, Clone, Copy, PartialEq, Debug)]
 #[godot(via = i64)]
-enum PopupPosition {
+pub enum PopupPosition {
     #[default]
     TopLeft,
     TopCenter,
@@ -39,20 +42,170 @@ impl PopupPosition {
         ];
         POPUP_POSITIONS.iter()
     }
+
+    /// Parse a snake case position name, e.g. `"right_top"`.
+    pub fn from_name(name: &str) -> Option<PopupPosition> {
+        match name {
+            "top_left" => Some(PopupPosition::TopLeft),
+            "top_center" => Some(PopupPosition::TopCenter),
+            "top_right" => Some(PopupPosition::TopRight),
+            "bottom_left" => Some(PopupPosition::BottomLeft),
+            "bottom_center" => Some(PopupPosition::BottomCenter),
+            "bottom_right" => Some(PopupPosition::BottomRight),
+            "left_top" => Some(PopupPosition::LeftTop),
+            "left_center" => Some(PopupPosition::LeftCenter),
+            "left_bottom" => Some(PopupPosition::LeftBottom),
+            "right_top" => Some(PopupPosition::RightTop),
+            "right_center" => Some(PopupPosition::RightCenter),
+            "right_bottom" => Some(PopupPosition::RightBottom),
+            _ => None,
+        }
+    }
+
+    /// Same alignment on the opposite side of the target.
+    fn flipped(&self) -> PopupPosition {
+        match self {
+            PopupPosition::TopLeft => PopupPosition::BottomLeft,
+            PopupPosition::TopCenter => PopupPosition::BottomCenter,
+            PopupPosition::TopRight => PopupPosition::BottomRight,
+            PopupPosition::BottomLeft => PopupPosition::TopLeft,
+            PopupPosition::BottomCenter => PopupPosition::TopCenter,
+            PopupPosition::BottomRight => PopupPosition::TopRight,
+            PopupPosition::LeftTop => PopupPosition::RightTop,
+            PopupPosition::LeftCenter => PopupPosition::RightCenter,
+            PopupPosition::LeftBottom => PopupPosition::RightBottom,
+            PopupPosition::RightTop => PopupPosition::LeftTop,
+            PopupPosition::RightCenter => PopupPosition::LeftCenter,
+            PopupPosition::RightBottom => PopupPosition::LeftBottom,
+        }
+    }
+
+    /// Side of the popup facing the target.
+    fn get_facing_side(&self) -> Side {
+        match self {
+            PopupPosition::TopLeft | PopupPosition::TopCenter | PopupPosition::TopRight => Side::BOTTOM,
+            PopupPosition::BottomLeft | PopupPosition::BottomCenter | PopupPosition::BottomRight => Side::TOP,
+            PopupPosition::LeftTop | PopupPosition::LeftCenter | PopupPosition::LeftBottom => Side::RIGHT,
+            PopupPosition::RightTop | PopupPosition::RightCenter | PopupPosition::RightBottom => Side::LEFT,
+        }
+    }
+}
+
+/// What the popup is placed next to.
+#[derive(GodotConvert, Var, Export, Default, Clone, Copy, PartialEq, Debug)]
+#[godot(via = i64)]
+pub enum PopupAnchor {
+    /// The `target` node.
+    #[default]
+    Target,
+    /// `anchor_point`, in canvas coordinates.
+    Point,
+    /// `anchor_rect`, in canvas coordinates.
+    Rect,
+    /// The mouse cursor.
+    Mouse,
+    /// The union of the rects of `anchor_nodes`.
+    Group,
+    /// The cutout of `anchor_focused_node`.
+    FocusedNode,
+}
+
+impl PopupAnchor {
+    /// Parse a snake case anchor name, e.g. `"focused_node"`.
+    pub fn from_name(name: &str) -> Option<PopupAnchor> {
+        match name {
+            "target" => Some(PopupAnchor::Target),
+            "point" => Some(PopupAnchor::Point),
+            "rect" => Some(PopupAnchor::Rect),
+            "mouse" => Some(PopupAnchor::Mouse),
+            "group" => Some(PopupAnchor::Group),
+            "focused_node" => Some(PopupAnchor::FocusedNode),
+            _ => None,
+        }
+    }
 }
 
 #[derive(GodotClass)]
 #[class(base = Container, tool)]
-struct PopupContainer {
+pub struct PopupContainer {
     base: Base<Container>,
     #[export]
-    #[var(hint = NODE_PATH_VALID_TYPES, hint_string = "Control")]
+    #[var(hint = NODE_PATH_VALID_TYPES, hint_string = "Control,Node2D,Node3D")]
     pub target: NodePath,
     #[export]
     pub position: PopupPosition,
+    /// What the popup is placed next to. `target`, page targets & `use_cutout_rect` only apply to `Target`.
+    #[export]
+    pub anchor: PopupAnchor,
+    #[export]
+    pub anchor_point: Vector2,
+    #[export]
+    pub anchor_rect: Rect2,
+    #[export]
+    pub anchor_nodes: Array<NodePath>,
+    /// Uses the same rect as the `TheaterRect` cutout when this popup is inside the `TheaterRect`.
+    #[export]
+    pub anchor_focused_node: Option<Gd<FocusedNode>>,
     /// Enable smart positioning.
     #[export]
     pub smart_position: bool,
+    /// Draw an arrow pointing at the target. Uses the theme constants `arrow_size` & `arrow_offset`,
+    /// and the theme color `arrow_color` or the background color of the child's `StyleBoxFlat`.
+    #[export]
+    pub show_arrow: bool,
+    /// Space between the target & the popup, per side of the target the popup is placed at.
+    #[export_group(name = "Spacing")]
+    #[export(range = (0.0, 100.0, or_greater, suffix = "px"))]
+    pub gap_left: f32,
+    #[export(range = (0.0, 100.0, or_greater, suffix = "px"))]
+    pub gap_top: f32,
+    #[export(range = (0.0, 100.0, or_greater, suffix = "px"))]
+    pub gap_right: f32,
+    #[export(range = (0.0, 100.0, or_greater, suffix = "px"))]
+    pub gap_bottom: f32,
+    /// Added to the position of the popup.
+    #[export]
+    pub position_offset: Vector2,
+    /// Distance smart positioning keeps the popup from the viewport edges.
+    #[export(range = (0.0, 100.0, or_greater, suffix = "px"))]
+    pub viewport_margin: f32,
+    /// Place the popup around the cutout of the target in the parent `TheaterRect`, including the
+    /// overlay's expand margins, instead of the target itself. Only used with the `Target` anchor.
+    #[export]
+    pub use_cutout_rect: bool,
+    /// Try the opposite side of the target when the popup doesn't fit.
+    #[export_group(name = "Smart Positioning")]
+    #[export]
+    pub flip: bool,
+    /// Slide the popup along the side of the target to stay visible.
+    #[export]
+    pub shift: bool,
+    /// Move the popup into the viewport when no position fits, even if it covers the target.
+    #[export]
+    pub clamp: bool,
+    /// Limit the popup to the space available next to the target, clipping the rest.
+    #[export]
+    pub auto_size: bool,
+    /// Avoid covering the cutouts of the parent `TheaterRect` & visible `PopupContainer` siblings before this one.
+    #[export]
+    pub avoid_obstacles: bool,
+    /// `PopupPosition`s to try in order after `position` & its flip. Tries all positions when empty.
+    #[export]
+    #[var(
+        get,
+        set = set_fallback_positions,
+        hint = TYPE_STRING,
+        hint_string = "2/2:Top Left,Top Center,Top Right,Bottom Left,Bottom Center,Bottom Right,Left Top,Left Center,Left Bottom,Right Top,Right Center,Right Bottom"
+    )]
+    pub fallback_positions: PackedInt32Array,
+    // Position used in the last update, differs from `position` when smart positioning picked another one.
+    displayed_position: PopupPosition,
+    /// Index of the shown child. Each `Control` child is a page, with optional `popup_target` &
+    /// `popup_position` metadata overriding `target` & `position`. `popup_target` is relative to this popup.
+    #[var(get = get_current_page, set = set_current_page)]
+    current_page: i64,
+    // Seconds left of the running shake.
+    shake_time_left: f64,
 }
 
 #[godot_api]
@@ -62,17 +215,56 @@ impl IContainer for PopupContainer {
             base,
             target: NodePath::default(),
             position: PopupPosition::RightTop,
+            anchor: PopupAnchor::Target,
+            anchor_point: Vector2::ZERO,
+            anchor_rect: Rect2::default(),
+            anchor_nodes: Array::new(),
+            anchor_focused_node: None,
             smart_position: true,
+            show_arrow: false,
+            gap_left: 0.0,
+            gap_top: 0.0,
+            gap_right: 0.0,
+            gap_bottom: 0.0,
+            position_offset: Vector2::ZERO,
+            viewport_margin: 0.0,
+            use_cutout_rect: false,
+            flip: true,
+            shift: true,
+            clamp: false,
+            auto_size: false,
+            avoid_obstacles: true,
+            fallback_positions: PackedInt32Array::new(),
+            displayed_position: PopupPosition::RightTop,
+            current_page: 0,
+            shake_time_left: 0.0,
         }
     }
 
     fn ready(&mut self) {
+        // Start at the page shown in the scene.
+        let pages = self.get_pages();
+        if let Some(page) = pages.iter().position(|page| page.is_visible()) {
+            self.current_page = page as i64;
+        }
     }
 
-    fn process(&mut self, _delta: f64) {
+    fn process(&mut self, delta: f64) {
+        self.shake_time_left = (self.shake_time_left - delta).max(0.0);
         self.update();
     }
 
+    fn draw(&mut self) {
+        if !self.show_arrow {
+            return;
+        }
+        let Some(arrow) = self.get_arrow_polygon() else {
+            return;
+        };
+        let color = self.get_arrow_color();
+        self.base_mut().draw_colored_polygon(&arrow, color);
+    }
+
     fn on_notification(&mut self, what: notify::ContainerNotification) {
         match what {
             notify::ContainerNotification::EDITOR_PRE_SAVE => {
@@ -80,6 +272,7 @@ impl IContainer for PopupContainer {
                 self.base_mut().set_custom_minimum_size(Vector2::default());
             },
             notify::ContainerNotification::EDITOR_POST_SAVE => {},
+            notify::ContainerNotification::CHILD_ORDER_CHANGED => self.clamp_current_page(),
             _ => {}
         }
     }
@@ -99,6 +292,127 @@ impl IContainer for PopupContainer {
 
 #[godot_api]
 impl PopupContainer {
+    // Page metadata overriding `target` & `position`.
+    const TARGET_META: &'static str = "popup_target";
+    const POSITION_META: &'static str = "popup_position";
+    // Arrow size without an `arrow_size` theme constant, in pixels.
+    const DEFAULT_ARROW_SIZE: f32 = 8.0;
+    const SHAKE_DURATION: f64 = 0.4;
+    // Offset at the start of a shake, in pixels.
+    const SHAKE_AMPLITUDE: f64 = 8.0;
+    // Speed of the shake, in radians per second.
+    const SHAKE_FREQUENCY: f64 = 40.0;
+
+    /// Emitted when the popup is placed at another position, e.g. because smart positioning flipped it.
+    #[signal]
+    fn placement_changed(position: PopupPosition);
+
+    /// Set the fallback positions, ignoring values that aren't a `PopupPosition`.
+    #[func]
+    pub fn set_fallback_positions(&mut self, positions: PackedInt32Array) {
+        let position_count = PopupPosition::iterator().len() as i32;
+        let valid_positions: Vec<i32> = positions.as_slice().iter().copied().filter(|index| {
+            let is_valid = (0..position_count).contains(index);
+            if !is_valid {
+                godot_warn!("PopupContainer: {index} isn't a PopupPosition, ignored.");
+            }
+            is_valid
+        }).collect();
+        self.fallback_positions = PackedInt32Array::from(valid_positions.as_slice());
+    }
+
+    /// Position the popup is currently placed at.
+    #[func]
+    pub fn get_placement(&self) -> PopupPosition {
+        self.displayed_position
+    }
+
+    // region: Pages
+
+    /// Emitted when another page is shown.
+    #[signal]
+    fn page_changed(page: i64);
+
+    #[func]
+    pub fn get_current_page(&self) -> i64 {
+        self.current_page
+    }
+
+    /// Show the page at `page` & hide the others. Clamped to the existing pages.
+    #[func]
+    pub fn set_current_page(&mut self, page: i64) {
+        let pages = self.get_pages();
+        if pages.is_empty() {
+            self.current_page = 0;
+            return;
+        }
+        let page = page.clamp(0, pages.len() as i64 - 1);
+        for (index, mut control) in pages.into_iter().enumerate() {
+            control.set_visible(index as i64 == page);
+        }
+        if page != self.current_page {
+            self.current_page = page;
+            self.base_mut().emit_signal("page_changed", &[page.to_variant()]);
+        }
+    }
+
+    /// Show the next page. Returns false on the last page.
+    #[func]
+    pub fn next_page(&mut self) -> bool {
+        if self.current_page + 1 >= self.page_count() {
+            return false;
+        }
+        self.set_current_page(self.current_page + 1);
+        true
+    }
+
+    /// Show the previous page. Returns false on the first page.
+    #[func]
+    pub fn previous_page(&mut self) -> bool {
+        if self.current_page <= 0 {
+            return false;
+        }
+        self.set_current_page(self.current_page - 1);
+        true
+    }
+
+    #[func]
+    pub fn page_count(&self) -> i64 {
+        self.get_pages().len() as i64
+    }
+
+    /// Keep `current_page` pointing at an existing page after pages were added, removed or moved.
+    fn clamp_current_page(&mut self) {
+        let page = self.current_page.clamp(0, (self.page_count() - 1).max(0));
+        if page != self.current_page {
+            self.current_page = page;
+            self.base_mut().emit_signal("page_changed", &[page.to_variant()]);
+        }
+    }
+
+    fn get_pages(&self) -> Vec<Gd<Control>> {
+        self.base().get_children().iter_shared()
+            .filter_map(|child| child.try_cast::<Control>().ok())
+            .collect()
+    }
+
+    // endregion: Pages
+
+    /// Shake horizontally to draw attention.
+    #[func]
+    pub fn shake(&mut self) {
+        self.shake_time_left = Self::SHAKE_DURATION;
+    }
+
+    fn get_shake_offset(&self) -> Vector2 {
+        if self.shake_time_left <= 0.0 {
+            return Vector2::ZERO;
+        }
+        let elapsed = Self::SHAKE_DURATION - self.shake_time_left;
+        let strength = self.shake_time_left / Self::SHAKE_DURATION;
+        Vector2::new(((elapsed * Self::SHAKE_FREQUENCY).sin() * Self::SHAKE_AMPLITUDE * strength) as f32, 0.0)
+    }
+
     fn reset(&mut self) {
         self.base_mut().set_size(Vector2::default());
         self.base_mut().set_position(Vector2::default());
@@ -106,21 +420,34 @@ impl PopupContainer {
 
     fn update(&mut self) {
         self.base_mut().set_custom_minimum_size(Vector2::default());
-        self.update_position();
-        let child_restult = self.get_child();
-        if let Some(child) = child_restult {
-            self.update_size(child.clone());
+        let child = self.get_child();
+        if let Some(child) = &child {
             self.update_child_control(child.clone());
         }
+        // Placement is chosen with the size the popup wants, not the size auto_size limited it to.
+        let natural_size = child.map(|child| child.get_size()).unwrap_or_else(|| self.base().get_size());
+        let (rect, position) = self.compute_placement(natural_size);
+        self.update_size(rect.size, natural_size);
+        self.update_position(rect.position, position);
     }
 
-    fn update_position(&mut self) {
-        let popup_position = if self.smart_position { self.get_position_smart() } else { self.get_popup_position(&self.position) };
-        self.base_mut().set_position(popup_position);
+    fn update_position(&mut self, popup_position: Vector2, position: PopupPosition) {
+        if self.displayed_position != position {
+            self.displayed_position = position;
+            self.base_mut().emit_signal("placement_changed", &[position.to_variant()]);
+        }
+        let shake_offset = self.get_shake_offset();
+        self.base_mut().set_position(popup_position + shake_offset);
+        if self.show_arrow {
+            self.base_mut().queue_redraw();
+        }
     }
 
-    fn update_size(&mut self, child: Gd<Control>) {
-        self.base_mut().set_size(child.get_size());
+    fn update_size(&mut self, size: Vector2, natural_size: Vector2) {
+        if self.smart_position && self.auto_size {
+            self.base_mut().set_clip_contents(size != natural_size);
+        }
+        self.base_mut().set_size(size);
     }
 
     fn update_child_control(&self, mut child: Gd<Control>) {
@@ -130,32 +457,237 @@ impl PopupContainer {
         child.set_size(Vector2::default());
     }
 
-    fn get_position_smart(&self) -> Vector2 {
-        if let Some(viewport) = self.base().get_viewport() {
-            let viewport_rect = viewport.get_visible_rect();
-            let preferred_rect = self.get_popup_rect(&self.position);
-            let preferred_area = self.check_popup_position(viewport_rect, preferred_rect);
-            let areas = PopupPosition::iterator().map(|position| {
-                let popup_rect = self.get_popup_rect(position);
-                let area = self.check_popup_position(viewport_rect, popup_rect);
-                (popup_rect, area)
-            });
-            let max_area = areas.max_by(|x, y| {
-                x.1.partial_cmp(&y.1).unwrap_or(Ordering::Equal)
-            });
-            if let Some((rect, area)) = max_area {
-                return if preferred_area >= area { preferred_rect.position } else { rect.position };
+    /// Place a popup of `size`, with smart positioning if enabled.
+    /// With auto_size the rect is limited to the space available at the chosen position.
+    fn compute_placement(&self, size: Vector2) -> (Rect2, PopupPosition) {
+        if !self.smart_position {
+            let position = self.get_preferred_position();
+            return (self.get_popup_rect(&position, size), position);
+        }
+        let (rect, position) = self.get_position_smart(size);
+        if !self.auto_size {
+            return (rect, position);
+        }
+        let (Some(available_size), Some(bounds), Some(target_rect)) =
+            (self.get_available_size(&position), self.get_bounds(), self.get_anchor_rect()) else {
+            return (rect, position);
+        };
+        let fitted_size = size.coord_min(available_size.coord_max(Vector2::ZERO));
+        if fitted_size == size {
+            return (rect, position);
+        }
+        let mut fitted_rect = self.get_shifted_rect(&position, fitted_size, target_rect, bounds);
+        if self.clamp {
+            fitted_rect = clamp_rect(fitted_rect, bounds);
+        }
+        (fitted_rect, position)
+    }
+
+    /// Rect of the popup & the `PopupPosition` it was placed at, for a popup of `size`.
+    /// Takes the first candidate that fits into the viewport without covering an obstacle, shifted along
+    /// the target if enabled. Otherwise the candidate with the largest visible area minus the area of the
+    /// obstacles it covers, clamped into the viewport if enabled.
+    fn get_position_smart(&self, size: Vector2) -> (Rect2, PopupPosition) {
+        let preferred_position = self.get_preferred_position();
+        let (Some(bounds), Some(target_rect)) = (self.get_bounds(), self.get_anchor_rect()) else {
+            return (self.get_popup_rect(&preferred_position, size), preferred_position);
+        };
+        let obstacles = if self.avoid_obstacles { self.get_obstacles() } else { Vec::new() };
+        let placements: Vec<(Rect2, PopupPosition)> = self.get_candidates(preferred_position).into_iter().map(|position| {
+            (self.get_shifted_rect(&position, size, target_rect, bounds), position)
+        }).collect();
+        let fits = |rect: &Rect2| bounds.encloses(*rect) && get_covered_area(*rect, &obstacles) <= 0.0;
+        if let Some(placement) = placements.iter().find(|(rect, _)| fits(rect)) {
+            return *placement;
+        }
+        // Keeps the earlier candidate on equal scores.
+        let best = placements.into_iter().fold(None, |best: Option<(Rect2, PopupPosition, f32)>, (rect, position)| {
+            let score = self.check_popup_position(bounds, rect) - get_covered_area(rect, &obstacles);
+            match best {
+                Some(best) if best.2 >= score => Some(best),
+                _ => Some((rect, position, score)),
             }
+        });
+        let Some((mut rect, position, _)) = best else {
+            return (self.get_popup_rect(&preferred_position, size), preferred_position);
+        };
+        if self.clamp {
+            rect = clamp_rect(rect, bounds);
         }
-        Vector2::default()
+        (rect, position)
+    }
+
+    /// Rect of a popup of `size` at `position`, shifted along the target if enabled.
+    fn get_shifted_rect(&self, position: &PopupPosition, size: Vector2, target_rect: Rect2, bounds: Rect2) -> Rect2 {
+        let rect = self.get_popup_rect(position, size);
+        if !self.shift {
+            return rect;
+        }
+        shift_rect(rect, target_rect, bounds, position.get_facing_side())
+    }
+
+    /// Visible rect of the viewport inside the viewport margin.
+    fn get_bounds(&self) -> Option<Rect2> {
+        let viewport = self.base().get_viewport()?;
+        Some(viewport.get_visible_rect().grow(-self.viewport_margin))
     }
 
-    fn get_popup_position(&self, position: &PopupPosition) -> Vector2 {
-        if let Some(target) = self.base().try_get_node_as::<Control>(&self.target) {
-            let global_rect = target.get_global_rect();
+    /// Cutouts of the parent `TheaterRect` other than the target's & rects of visible `PopupContainer`
+    /// siblings before this one. Later siblings avoid earlier ones, so two popups don't keep pushing each other away.
+    fn get_obstacles(&self) -> Vec<Rect2> {
+        let mut obstacles = Vec::new();
+        if let Some(theater_rect) = self.get_theater_rect() {
+            let theater_rect = theater_rect.bind();
+            let target_cutout_rect = self.get_anchor_cutout_rect(&theater_rect);
+            obstacles.extend(theater_rect.get_cutout_rects().into_iter().filter(|rect| Some(*rect) != target_cutout_rect));
+        }
+        let Some(parent) = self.base().get_parent() else {
+            return obstacles;
+        };
+        let index = self.base().get_index();
+        for sibling in parent.get_children().iter_shared().take(index.max(0) as usize) {
+            if let Ok(popup) = sibling.try_cast::<PopupContainer>() {
+                if popup.is_visible_in_tree() {
+                    obstacles.push(popup.get_global_rect());
+                }
+            }
+        }
+        obstacles
+    }
+
+    /// Positions smart positioning tries, in order.
+    fn get_candidates(&self, preferred_position: PopupPosition) -> Vec<PopupPosition> {
+        let mut candidates = vec![preferred_position];
+        if self.flip {
+            candidates.push(preferred_position.flipped());
+        }
+        if self.fallback_positions.is_empty() {
+            candidates.extend(PopupPosition::iterator().copied());
+        } else {
+            candidates.extend(self.fallback_positions.as_slice().iter().filter_map(|index| {
+                PopupPosition::iterator().nth(usize::try_from(*index).ok()?).copied()
+            }));
+        }
+        let mut unique_candidates = Vec::new();
+        for candidate in candidates {
+            if !unique_candidates.contains(&candidate) {
+                unique_candidates.push(candidate);
+            }
+        }
+        unique_candidates
+    }
+
+    /// Space next to the target on the side of `position`, inside the viewport margin.
+    fn get_available_size(&self, position: &PopupPosition) -> Option<Vector2> {
+        let bounds = self.get_bounds()?;
+        let target_rect = self.get_anchor_rect()?;
+        let gap = self.get_gap(position).abs();
+        Some(match position.get_facing_side() {
+            Side::BOTTOM => Vector2::new(bounds.size.x, target_rect.position.y - gap.y - bounds.position.y),
+            Side::TOP => Vector2::new(bounds.size.x, bounds.end().y - target_rect.end().y - gap.y),
+            Side::RIGHT => Vector2::new(target_rect.position.x - gap.x - bounds.position.x, bounds.size.y),
+            _ => Vector2::new(bounds.end().x - target_rect.end().x - gap.x, bounds.size.y),
+        })
+    }
+
+    /// Target of the current page, or `target`.
+    /// Page metadata is a `NodePath` or `String`, resolved relative to this popup like `target`.
+    fn get_target(&self) -> Option<Gd<Node>> {
+        if let Some(page) = self.get_child() {
+            if page.has_meta(Self::TARGET_META) {
+                let path = page.get_meta(Self::TARGET_META);
+                let path = path.try_to::<NodePath>()
+                    .or_else(|_| path.try_to::<GString>().map(|path| NodePath::from(&path)))
+                    .ok()?;
+                return self.base().get_node_or_null(&path);
+            }
+        }
+        self.base().get_node_or_null(&self.target)
+    }
+
+    /// Position of the current page, or `position`. Page metadata is a `PopupPosition` or its snake case name.
+    fn get_preferred_position(&self) -> PopupPosition {
+        self.get_child()
+            .filter(|page| page.has_meta(Self::POSITION_META))
+            .and_then(|page| {
+                let position = page.get_meta(Self::POSITION_META);
+                if let Ok(name) = position.try_to::<GString>() {
+                    return PopupPosition::from_name(&name.to_string());
+                }
+                position.try_to::<PopupPosition>().ok()
+            })
+            .unwrap_or(self.position)
+    }
+
+    /// Rect the popup is placed next to, in canvas coordinates.
+    fn get_anchor_rect(&self) -> Option<Rect2> {
+        match self.anchor {
+            PopupAnchor::Target => {
+                let target = self.get_target()?;
+                if self.use_cutout_rect {
+                    let cutout_rect = self.get_theater_rect()
+                        .and_then(|theater_rect| theater_rect.bind().get_cutout_rect_of(&target));
+                    if let Some(cutout_rect) = cutout_rect {
+                        return Some(cutout_rect);
+                    }
+                }
+                self.get_node_rect(&target)
+            }
+            PopupAnchor::Point => Some(Rect2::new(self.anchor_point, Vector2::ZERO)),
+            PopupAnchor::Rect => Some(self.anchor_rect),
+            PopupAnchor::Mouse => Some(Rect2::new(self.base().get_global_mouse_position(), Vector2::ZERO)),
+            PopupAnchor::Group => self.anchor_nodes.iter_shared()
+                .filter_map(|path| self.base().get_node_or_null(&path))
+                .filter(target_rect::is_target_visible)
+                .filter_map(|node| self.get_node_rect(&node))
+                .reduce(|union, rect| union.merge(rect)),
+            PopupAnchor::FocusedNode => {
+                let focused_node = self.anchor_focused_node.clone()?;
+                let theater_rect = self.get_theater_rect()
+                    .filter(|theater_rect| theater_rect.bind().focused_nodes.contains(Some(&focused_node)));
+                if let Some(theater_rect) = theater_rect {
+                    return theater_rect.bind().get_focused_node_rect(&focused_node);
+                }
+                // Not one of the TheaterRect's focused nodes, resolve it like the TheaterRect would.
+                let target = focused_node.clone().bind_mut().resolve(&self.base().clone().upcast())?;
+                self.get_node_rect(&target)
+            }
+        }
+    }
+
+    /// Cutout of the parent `TheaterRect` the popup is anchored to, if any.
+    fn get_anchor_cutout_rect(&self, theater_rect: &TheaterRect) -> Option<Rect2> {
+        match self.anchor {
+            PopupAnchor::Target => theater_rect.get_cutout_rect_of(&self.get_target()?),
+            PopupAnchor::FocusedNode => theater_rect.get_focused_node_rect(self.anchor_focused_node.as_ref()?),
+            _ => None,
+        }
+    }
+
+    /// Rect of a `Control`, `Node2D` or `Node3D` in the canvas coordinates of this node.
+    fn get_node_rect(&self, node: &Gd<Node>) -> Option<Rect2> {
+        let viewport = self.base().get_viewport()?;
+        let viewport_rect = target_rect::get_rect_in_viewport(node, &viewport)?;
+        let canvas_transform = self.base().get_canvas_transform().affine_inverse();
+        Some(target_rect::transform_rect(canvas_transform, viewport_rect))
+    }
+
+    fn get_theater_rect(&self) -> Option<Gd<TheaterRect>> {
+        let mut node = self.base().get_parent();
+        while let Some(parent) = node {
+            if let Ok(theater_rect) = parent.clone().try_cast::<TheaterRect>() {
+                return Some(theater_rect);
+            }
+            node = parent.get_parent();
+        }
+        None
+    }
+
+    fn get_popup_position(&self, position: &PopupPosition, size: Vector2) -> Vector2 {
+        if let Some(global_rect) = self.get_anchor_rect() {
             let global_center = global_rect.position + (global_rect.size / 2.0);
-            let size = self.base().get_size();
-            return match position {
+            let gap = self.get_gap(position);
+            return gap + self.position_offset + match position {
                 PopupPosition::TopLeft => global_rect.position - Vector2::new(0.0, size.y),
                 PopupPosition::TopCenter => Vector2::new(global_center.x, global_rect.position.y) - Vector2::new(size.x / 2.0, size.y),
                 PopupPosition::TopRight => Vector2::new(global_rect.position.x + global_rect.size.x, global_rect.position.y) - Vector2::new(size.x, size.y),
@@ -173,9 +705,85 @@ impl PopupContainer {
         Vector2::default()
     }
 
-    fn get_popup_rect(&self, position: &PopupPosition) -> Rect2 {
-        let popup_position = self.get_popup_position(position);
-        return Rect2::new(popup_position, self.base().get_size());
+    /// Space between the target & the popup, including the arrow.
+    fn get_gap(&self, position: &PopupPosition) -> Vector2 {
+        let arrow_size = if self.show_arrow { self.get_arrow_size() } else { 0.0 };
+        match position.get_facing_side() {
+            Side::BOTTOM => Vector2::new(0.0, -(arrow_size + self.gap_top)),
+            Side::TOP => Vector2::new(0.0, arrow_size + self.gap_bottom),
+            Side::RIGHT => Vector2::new(-(arrow_size + self.gap_left), 0.0),
+            _ => Vector2::new(arrow_size + self.gap_right, 0.0),
+        }
+    }
+
+    fn get_arrow_size(&self) -> f32 {
+        if self.base().has_theme_constant("arrow_size") {
+            return self.base().get_theme_constant("arrow_size") as f32;
+        }
+        Self::DEFAULT_ARROW_SIZE
+    }
+
+    fn get_arrow_color(&self) -> Color {
+        if self.base().has_theme_color("arrow_color") {
+            return self.base().get_theme_color("arrow_color");
+        }
+        self.get_child()
+            .and_then(|child| child.get_theme_stylebox("panel"))
+            .and_then(|stylebox| stylebox.try_cast::<StyleBoxFlat>().ok())
+            .map(|stylebox| stylebox.get_bg_color())
+            .unwrap_or(Color::WHITE)
+    }
+
+    /// Triangle on the side facing the target, pointing at the target's center and kept within the
+    /// side's rounded corners. `arrow_offset` moves it along the side.
+    fn get_arrow_polygon(&self) -> Option<PackedVector2Array> {
+        let to_local = self.base().get_global_transform().affine_inverse();
+        let target_center = to_local * self.get_anchor_rect()?.center();
+        let size = self.base().get_size();
+        let arrow_size = self.get_arrow_size();
+        if arrow_size <= 0.0 || size.x <= 0.0 || size.y <= 0.0 {
+            return None;
+        }
+        let arrow_offset = self.base().get_theme_constant("arrow_offset") as f32;
+        let corner_radius = self.get_child()
+            .and_then(|child| child.get_theme_stylebox("panel"))
+            .and_then(|stylebox| stylebox.try_cast::<StyleBoxFlat>().ok())
+            .map(|stylebox| {
+                [Corner::TOP_LEFT, Corner::TOP_RIGHT, Corner::BOTTOM_RIGHT, Corner::BOTTOM_LEFT].into_iter()
+                    .map(|corner| stylebox.get_corner_radius(corner))
+                    .max()
+                    .unwrap_or(0) as f32
+            })
+            .unwrap_or(0.0);
+        let along = |center: f32, length: f32| {
+            let margin = (arrow_size + corner_radius).min(length / 2.0);
+            (center + arrow_offset).clamp(margin, length - margin)
+        };
+        // Base overlaps the popup by a pixel to avoid a seam.
+        let points = match self.displayed_position.get_facing_side() {
+            Side::BOTTOM => {
+                let x = along(target_center.x, size.x);
+                [Vector2::new(x - arrow_size, size.y - 1.0), Vector2::new(x, size.y + arrow_size), Vector2::new(x + arrow_size, size.y - 1.0)]
+            }
+            Side::TOP => {
+                let x = along(target_center.x, size.x);
+                [Vector2::new(x + arrow_size, 1.0), Vector2::new(x, -arrow_size), Vector2::new(x - arrow_size, 1.0)]
+            }
+            Side::RIGHT => {
+                let y = along(target_center.y, size.y);
+                [Vector2::new(size.x - 1.0, y + arrow_size), Vector2::new(size.x + arrow_size, y), Vector2::new(size.x - 1.0, y - arrow_size)]
+            }
+            _ => {
+                let y = along(target_center.y, size.y);
+                [Vector2::new(1.0, y - arrow_size), Vector2::new(-arrow_size, y), Vector2::new(1.0, y + arrow_size)]
+            }
+        };
+        Some(PackedVector2Array::from(points.as_slice()))
+    }
+
+    fn get_popup_rect(&self, position: &PopupPosition, size: Vector2) -> Rect2 {
+        let popup_position = self.get_popup_position(position, size);
+        return Rect2::new(popup_position, size);
     }
     
     fn check_popup_position(&self, viewport_rect: Rect2, popup_rect: Rect2) -> f32 {
@@ -186,15 +794,43 @@ impl PopupContainer {
         -1.0
     }
 
+    /// The current page, unless it's hidden.
     fn get_child(&self) -> Option<Gd<Control>> {
-        let children = self.base().get_children();
-        for child in children.iter_shared() {
-            if let Ok(control) = child.try_cast::<Control>() {
-                if control.is_visible() {
-                    return Some(control);
-                }
-            }
-        }
-        None
+        let page = self.get_pages().into_iter().nth(usize::try_from(self.current_page).ok()?)?;
+        page.is_visible().then_some(page)
     }
 }
+
+/// Slide `rect` along the side of the target into `bounds`, keeping it next to the target.
+fn shift_rect(rect: Rect2, target_rect: Rect2, bounds: Rect2, facing_side: Side) -> Rect2 {
+    let mut position = rect.position;
+    if facing_side == Side::TOP || facing_side == Side::BOTTOM {
+        position.x = clamp_axis(position.x, rect.size.x, bounds.position.x, bounds.end().x)
+            .clamp(target_rect.position.x - rect.size.x, target_rect.end().x);
+    } else {
+        position.y = clamp_axis(position.y, rect.size.y, bounds.position.y, bounds.end().y)
+            .clamp(target_rect.position.y - rect.size.y, target_rect.end().y);
+    }
+    Rect2::new(position, rect.size)
+}
+
+/// Move `rect` into `bounds`. Aligns to the top left of `bounds` if it's too large.
+fn clamp_rect(rect: Rect2, bounds: Rect2) -> Rect2 {
+    let position = Vector2::new(
+        clamp_axis(rect.position.x, rect.size.x, bounds.position.x, bounds.end().x),
+        clamp_axis(rect.position.y, rect.size.y, bounds.position.y, bounds.end().y),
+    );
+    Rect2::new(position, rect.size)
+}
+
+/// Total area of `rect` covered by `obstacles`.
+fn get_covered_area(rect: Rect2, obstacles: &[Rect2]) -> f32 {
+    obstacles.iter()
+        .filter_map(|obstacle| rect.intersect(*obstacle))
+        .map(|intersection| intersection.area())
+        .sum()
+}
+
+fn clamp_axis(start: f32, length: f32, min: f32, max: f32) -> f32 {
+    start.min(max - length).max(min)
+}
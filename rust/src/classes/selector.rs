@@ -0,0 +1,371 @@
+use std::collections::HashSet;
+
+use godot::prelude::*;
+
+use super::target_rect;
+
+/// A parsed node selector, similar to a CSS selector.
+///
+/// - `Class` matches nodes inheriting the class, `*` matches any node.
+/// - `#name` matches the node name.
+/// - `[property]` matches nodes with a non-null property. `[property="value"]` compares the
+///   stringified property with `=` (equals), `*=` (contains), `^=` (starts with) or `$=` (ends with).
+/// - `:visible` matches nodes that are visible in the tree.
+/// - `:nth(n)` keeps only the n-th (zero based) match below each parent match.
+/// - `A B` matches `B` anywhere below `A`, `A > B` matches `B` as a direct child of `A`.
+///
+/// Internal children are included, e.g. `SceneTreeDock > SceneTreeEditor Tree`.
+#[derive(Clone, Debug)]
+pub struct Selector {
+    parts: Vec<(Combinator, Compound)>,
+}
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Combinator {
+    Descendant,
+    Child,
+}
+
+/// Conditions a single node has to match.
+#[derive(Clone, Default, Debug)]
+struct Compound {
+    class: Option<String>,
+    name: Option<String>,
+    attributes: Vec<Attribute>,
+    nth: Option<usize>,
+    visible: bool,
+}
+
+#[derive(Clone, Debug)]
+struct Attribute {
+    property: String,
+    operator: AttributeOperator,
+}
+
+#[derive(Clone, Debug)]
+enum AttributeOperator {
+    Exists,
+    Equals(String),
+    Contains(String),
+    StartsWith(String),
+    EndsWith(String),
+}
+
+impl Selector {
+    pub fn parse(source: &str) -> Result<Selector, String> {
+        Parser::new(source).parse()
+    }
+
+    /// Shortest chain of child selectors from `target` up towards `root` that finds `target` first.
+    /// None if `target` isn't below `root`.
+    pub fn generate(target: &Gd<Node>, root: &Gd<Node>) -> Option<String> {
+        let mut compounds = Vec::new();
+        let mut node = target.clone();
+        while node != *root {
+            let parent = node.get_parent()?;
+            compounds.push(describe(&node, &parent));
+            node = parent;
+        }
+        compounds.reverse();
+        (0..compounds.len()).rev().find_map(|start| {
+            let source = compounds[start..].join(" > ");
+            let selector = Selector::parse(&source).ok()?;
+            (selector.query(root).as_ref() == Some(target)).then_some(source)
+        })
+    }
+
+    /// First node below `root` matching the selector.
+    pub fn query(&self, root: &Gd<Node>) -> Option<Gd<Node>> {
+        self.query_all(root).into_iter().next()
+    }
+
+    /// All nodes below `root` matching the selector, in tree order.
+    pub fn query_all(&self, root: &Gd<Node>) -> Vec<Gd<Node>> {
+        let mut matches = vec![root.clone()];
+        for (combinator, compound) in &self.parts {
+            let mut next_matches: Vec<Gd<Node>> = Vec::new();
+            for node in &matches {
+                let mut candidates = Vec::new();
+                match combinator {
+                    Combinator::Child => candidates.extend(get_children(node)),
+                    Combinator::Descendant => collect_descendants(node, &mut candidates),
+                }
+                let mut found = candidates.into_iter().filter(|candidate| compound.matches(candidate));
+                match compound.nth {
+                    Some(nth) => next_matches.extend(found.nth(nth)),
+                    None => next_matches.extend(found),
+                }
+            }
+            // Nested matches of descendant combinators find the same nodes more than once.
+            let mut seen = HashSet::new();
+            next_matches.retain(|node| seen.insert(node.instance_id()));
+            matches = next_matches;
+            if matches.is_empty() {
+                break;
+            }
+        }
+        matches
+    }
+}
+
+impl Compound {
+    fn is_empty(&self) -> bool {
+        self.class.is_none() && self.name.is_none() && self.attributes.is_empty() && self.nth.is_none() && !self.visible
+    }
+
+    fn matches(&self, node: &Gd<Node>) -> bool {
+        if let Some(class) = &self.class {
+            if class != "*" && !node.is_class(class.as_str()) {
+                return false;
+            }
+        }
+        if let Some(name) = &self.name {
+            if node.get_name().to_string() != *name {
+                return false;
+            }
+        }
+        if self.visible && !target_rect::is_target_visible(node) {
+            return false;
+        }
+        self.attributes.iter().all(|attribute| attribute.matches(node))
+    }
+}
+
+impl Attribute {
+    fn matches(&self, node: &Gd<Node>) -> bool {
+        let value = node.get(self.property.as_str());
+        if value.is_nil() {
+            return false;
+        }
+        let value = value.stringify().to_string();
+        match &self.operator {
+            AttributeOperator::Exists => true,
+            AttributeOperator::Equals(expected) => value == *expected,
+            AttributeOperator::Contains(expected) => value.contains(expected.as_str()),
+            AttributeOperator::StartsWith(expected) => value.starts_with(expected.as_str()),
+            AttributeOperator::EndsWith(expected) => value.ends_with(expected.as_str()),
+        }
+    }
+}
+
+/// Compound selector for `node` among the children of `parent`.
+fn describe(node: &Gd<Node>, parent: &Gd<Node>) -> String {
+    let class = node.get_class();
+    let mut compound = class.to_string();
+    let name = node.get_name().to_string();
+    // Generated names like `@VBoxContainer@1234` change between editor sessions.
+    if !name.contains('@') {
+        compound += &format!("#{}", quote(&name));
+        return compound;
+    }
+    let siblings: Vec<Gd<Node>> = get_children(parent).into_iter().filter(|sibling| sibling.is_class(&class)).collect();
+    if siblings.len() > 1 {
+        if let Some(index) = siblings.iter().position(|sibling| sibling == node) {
+            compound += &format!(":nth({index})");
+        }
+    }
+    compound
+}
+
+/// `value` as an identifier, or quoted if it contains other characters.
+fn quote(value: &str) -> String {
+    if !value.is_empty() && value.chars().all(is_identifier_char) {
+        return value.to_string();
+    }
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+fn get_children(node: &Gd<Node>) -> Vec<Gd<Node>> {
+    node.get_children_ex().include_internal(true).done().iter_shared().collect()
+}
+
+fn collect_descendants(node: &Gd<Node>, descendants: &mut Vec<Gd<Node>>) {
+    for child in get_children(node) {
+        descendants.push(child.clone());
+        collect_descendants(&child, descendants);
+    }
+}
+
+struct Parser<'a> {
+    source: &'a str,
+    chars: std::iter::Peekable<std::str::CharIndices<'a>>,
+}
+
+impl<'a> Parser<'a> {
+    fn new(source: &'a str) -> Self {
+        Self {
+            source,
+            chars: source.char_indices().peekable(),
+        }
+    }
+
+    fn parse(mut self) -> Result<Selector, String> {
+        let mut parts = Vec::new();
+        let mut combinator = Combinator::Descendant;
+        loop {
+            let had_whitespace = self.skip_whitespace();
+            match self.peek() {
+                None => break,
+                Some('>') => {
+                    if parts.is_empty() || combinator == Combinator::Child {
+                        return Err(self.error("unexpected \">\""));
+                    }
+                    self.chars.next();
+                    combinator = Combinator::Child;
+                    continue;
+                }
+                Some(_) => {
+                    if !parts.is_empty() && !had_whitespace && combinator == Combinator::Descendant {
+                        return Err(self.error("expected whitespace or \">\""));
+                    }
+                }
+            }
+            let compound = self.parse_compound()?;
+            parts.push((combinator, compound));
+            combinator = Combinator::Descendant;
+        }
+        if parts.is_empty() {
+            return Err("empty selector".to_string());
+        }
+        if combinator == Combinator::Child {
+            return Err(self.error("expected a selector after \">\""));
+        }
+        Ok(Selector { parts })
+    }
+
+    fn parse_compound(&mut self) -> Result<Compound, String> {
+        let mut compound = Compound::default();
+        if self.peek() == Some('*') {
+            self.chars.next();
+            compound.class = Some("*".to_string());
+        } else if self.peek().is_some_and(is_identifier_char) {
+            compound.class = Some(self.parse_identifier()?);
+        }
+        loop {
+            match self.peek() {
+                Some('#') => {
+                    self.chars.next();
+                    compound.name = Some(self.parse_value()?);
+                }
+                Some('[') => {
+                    self.chars.next();
+                    compound.attributes.push(self.parse_attribute()?);
+                }
+                Some(':') => {
+                    self.chars.next();
+                    match self.parse_identifier()?.as_str() {
+                        "visible" => compound.visible = true,
+                        "nth" => {
+                            self.expect('(')?;
+                            let index = self.parse_value()?;
+                            compound.nth = Some(index.parse::<usize>()
+                                .map_err(|_| self.error(&format!("\"{index}\" isn't a valid index")))?);
+                            self.expect(')')?;
+                        }
+                        pseudo_class => return Err(self.error(&format!("unknown pseudo class \":{pseudo_class}\""))),
+                    }
+                }
+                _ => break,
+            }
+        }
+        if compound.is_empty() {
+            return Err(self.error("expected a selector"));
+        }
+        Ok(compound)
+    }
+
+    fn parse_attribute(&mut self) -> Result<Attribute, String> {
+        self.skip_whitespace();
+        let property = self.parse_identifier()?;
+        self.skip_whitespace();
+        let operator = match self.peek() {
+            Some(']') => {
+                self.chars.next();
+                return Ok(Attribute { property, operator: AttributeOperator::Exists });
+            }
+            Some('=') => {
+                self.chars.next();
+                AttributeOperator::Equals
+            }
+            Some(prefix @ ('*' | '^' | '$')) => {
+                self.chars.next();
+                self.expect('=')?;
+                match prefix {
+                    '*' => AttributeOperator::Contains,
+                    '^' => AttributeOperator::StartsWith,
+                    _ => AttributeOperator::EndsWith,
+                }
+            }
+            _ => return Err(self.error("expected \"]\" or an operator")),
+        };
+        self.skip_whitespace();
+        let value = self.parse_value()?;
+        self.skip_whitespace();
+        self.expect(']')?;
+        Ok(Attribute { property, operator: operator(value) })
+    }
+
+    /// A quoted string or an identifier.
+    fn parse_value(&mut self) -> Result<String, String> {
+        let Some(quote @ ('"' | '\'')) = self.peek() else {
+            return self.parse_identifier();
+        };
+        self.chars.next();
+        let mut value = String::new();
+        loop {
+            match self.chars.next() {
+                Some((_, '\\')) => match self.chars.next() {
+                    Some((_, escaped)) => value.push(escaped),
+                    None => break,
+                },
+                Some((_, character)) if character == quote => return Ok(value),
+                Some((_, character)) => value.push(character),
+                None => break,
+            }
+        }
+        Err(self.error("unterminated string"))
+    }
+
+    fn parse_identifier(&mut self) -> Result<String, String> {
+        let mut identifier = String::new();
+        while let Some(character) = self.peek().filter(|character| is_identifier_char(*character)) {
+            identifier.push(character);
+            self.chars.next();
+        }
+        if identifier.is_empty() {
+            return Err(self.error("expected an identifier"));
+        }
+        Ok(identifier)
+    }
+
+    fn expect(&mut self, expected: char) -> Result<(), String> {
+        if self.peek() != Some(expected) {
+            return Err(self.error(&format!("expected \"{expected}\"")));
+        }
+        self.chars.next();
+        Ok(())
+    }
+
+    /// Skip whitespace, returns whether any was skipped.
+    fn skip_whitespace(&mut self) -> bool {
+        let mut skipped = false;
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.chars.next();
+            skipped = true;
+        }
+        skipped
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.chars.peek().map(|(_, character)| *character)
+    }
+
+    fn error(&mut self, message: &str) -> String {
+        let position = self.chars.peek().map_or(self.source.len(), |(position, _)| *position);
+        format!("{message} at position {position} of \"{}\"", self.source)
+    }
+}
+
+fn is_identifier_char(character: char) -> bool {
+    character.is_alphanumeric() || matches!(character, '_' | '-' | '@')
+}
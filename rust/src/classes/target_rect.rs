@@ -0,0 +1,209 @@
+use godot::prelude::*;
+use godot::builtin::Side;
+use godot::classes::{
+    AnimatedSprite2D, CanvasItem, CollisionShape2D, CollisionShape3D, Control, Node2D, Node3D, Sprite2D,
+    StyleBox, StyleBoxFlat, StyleBoxTexture, SubViewport, SubViewportContainer, Viewport, VisualInstance3D, Window,
+};
+
+/// Rect of a `Control`, `Node2D` or `Node3D` in the coordinates of its viewport.
+/// `Node3D`s are projected through the viewport's active `Camera3D`.
+pub fn get_viewport_rect(target: &Gd<Node>) -> Option<Rect2> {
+    if let Ok(control) = target.clone().try_cast::<Control>() {
+        let rect = Rect2::new(Vector2::ZERO, control.get_size());
+        return Some(transform_rect(control.get_global_transform_with_canvas(), rect));
+    }
+    if let Ok(node_2d) = target.clone().try_cast::<Node2D>() {
+        let rect = get_node_2d_rect(&node_2d)?;
+        return Some(transform_rect(node_2d.get_global_transform_with_canvas(), rect));
+    }
+    if let Ok(node_3d) = target.clone().try_cast::<Node3D>() {
+        return get_node_3d_rect(&node_3d);
+    }
+    None
+}
+
+/// Rect of a target in the coordinates of `viewport`. Follows `SubViewportContainer`s & embedded `Window`s
+/// up to `viewport`. None if the target isn't displayed inside `viewport`, e.g. it's in another OS window.
+pub fn get_rect_in_viewport(target: &Gd<Node>, viewport: &Gd<Viewport>) -> Option<Rect2> {
+    let mut rect = get_viewport_rect(target)?;
+    let mut target_viewport = target.get_viewport()?;
+    while target_viewport != *viewport {
+        let (parent_rect, parent_viewport) = to_parent_viewport(rect, &target_viewport)?;
+        rect = parent_rect;
+        target_viewport = parent_viewport;
+    }
+    Some(rect)
+}
+
+/// The OS window a node is displayed in.
+pub fn get_native_window(node: &Gd<Node>) -> Option<Gd<Window>> {
+    let mut viewport = node.get_viewport()?;
+    loop {
+        if let Ok(window) = viewport.clone().try_cast::<Window>() {
+            if !window.is_embedded() {
+                return Some(window);
+            }
+        }
+        viewport = viewport.get_parent()?.get_viewport()?;
+    }
+}
+
+/// Map a rect of `viewport` into the viewport `viewport` is displayed in.
+fn to_parent_viewport(rect: Rect2, viewport: &Gd<Viewport>) -> Option<(Rect2, Gd<Viewport>)> {
+    if let Ok(window) = viewport.clone().try_cast::<Window>() {
+        // OS windows aren't displayed inside another viewport.
+        if !window.is_embedded() {
+            return None;
+        }
+        let position = window.get_position().cast_float();
+        let parent_viewport = window.get_parent()?.get_viewport()?;
+        return Some((Rect2::new(rect.position + position, rect.size), parent_viewport));
+    }
+    if let Ok(sub_viewport) = viewport.clone().try_cast::<SubViewport>() {
+        let container = sub_viewport.get_parent()?.try_cast::<SubViewportContainer>().ok()?;
+        let sub_viewport_size = sub_viewport.get_size().cast_float();
+        if sub_viewport_size.x <= 0.0 || sub_viewport_size.y <= 0.0 {
+            return None;
+        }
+        let scale = container.get_size() / sub_viewport_size;
+        let container_rect = Rect2::new(rect.position * scale, rect.size * scale);
+        let parent_viewport = container.get_viewport()?;
+        return Some((transform_rect(container.get_global_transform_with_canvas(), container_rect), parent_viewport));
+    }
+    None
+}
+
+/// Whether the target is visible in the tree. Nodes without visibility are always visible.
+pub fn is_target_visible(target: &Gd<Node>) -> bool {
+    if let Ok(canvas_item) = target.clone().try_cast::<CanvasItem>() {
+        return canvas_item.is_visible_in_tree();
+    }
+    if let Ok(node_3d) = target.clone().try_cast::<Node3D>() {
+        return node_3d.is_visible_in_tree();
+    }
+    true
+}
+
+/// Bounding rect of the transformed corners of `rect`.
+pub fn transform_rect(transform: Transform2D, rect: Rect2) -> Rect2 {
+    let corners = [
+        rect.position,
+        rect.position + Vector2::new(rect.size.x, 0.0),
+        rect.position + Vector2::new(0.0, rect.size.y),
+        rect.position + rect.size,
+    ];
+    get_bounding_rect(corners.iter().map(|corner| transform * *corner)).unwrap_or_default()
+}
+
+/// `rect` grown by the expand margins of a `StyleBoxFlat` or `StyleBoxTexture`, e.g. of an overlay.
+pub fn grow_by_expand_margins(rect: Rect2, stylebox: &Gd<StyleBox>) -> Rect2 {
+    if let Ok(flat_stylebox) = stylebox.clone().try_cast::<StyleBoxFlat>() {
+        return rect.grow_individual(
+            flat_stylebox.get_expand_margin(Side::LEFT),
+            flat_stylebox.get_expand_margin(Side::TOP),
+            flat_stylebox.get_expand_margin(Side::RIGHT),
+            flat_stylebox.get_expand_margin(Side::BOTTOM),
+        );
+    }
+    if let Ok(texture_stylebox) = stylebox.clone().try_cast::<StyleBoxTexture>() {
+        return rect.grow_individual(
+            texture_stylebox.get_expand_margin(Side::LEFT),
+            texture_stylebox.get_expand_margin(Side::TOP),
+            texture_stylebox.get_expand_margin(Side::RIGHT),
+            texture_stylebox.get_expand_margin(Side::BOTTOM),
+        );
+    }
+    rect
+}
+
+fn get_bounding_rect(points: impl Iterator<Item = Vector2>) -> Option<Rect2> {
+    points.fold(None, |bounds: Option<Rect2>, point| {
+        Some(match bounds {
+            Some(bounds) => bounds.expand(point),
+            None => Rect2::new(point, Vector2::ZERO),
+        })
+    })
+}
+
+/// Local rect of a `Node2D`, merged with the rects of its children.
+fn get_node_2d_rect(node_2d: &Gd<Node2D>) -> Option<Rect2> {
+    let mut rect = get_own_node_2d_rect(node_2d);
+    for child in node_2d.get_children().iter_shared() {
+        if let Ok(child_2d) = child.try_cast::<Node2D>() {
+            if !child_2d.is_visible() {
+                continue;
+            }
+            if let Some(child_rect) = get_node_2d_rect(&child_2d) {
+                let child_rect = transform_rect(child_2d.get_transform(), child_rect);
+                rect = Some(match rect {
+                    Some(rect) => rect.merge(child_rect),
+                    None => child_rect,
+                });
+            }
+        }
+    }
+    rect
+}
+
+fn get_own_node_2d_rect(node_2d: &Gd<Node2D>) -> Option<Rect2> {
+    if let Ok(sprite) = node_2d.clone().try_cast::<Sprite2D>() {
+        return Some(sprite.get_rect());
+    }
+    if let Ok(animated_sprite) = node_2d.clone().try_cast::<AnimatedSprite2D>() {
+        let sprite_frames = animated_sprite.get_sprite_frames()?;
+        let texture = sprite_frames.get_frame_texture(&animated_sprite.get_animation(), animated_sprite.get_frame())?;
+        let size = texture.get_size();
+        let mut position = animated_sprite.get_offset();
+        if animated_sprite.is_centered() {
+            position -= size / 2.0;
+        }
+        return Some(Rect2::new(position, size));
+    }
+    if let Ok(collision_shape) = node_2d.clone().try_cast::<CollisionShape2D>() {
+        return Some(collision_shape.get_shape()?.get_rect());
+    }
+    None
+}
+
+/// Screen rect of a `Node3D` and its children, projected through the active camera.
+fn get_node_3d_rect(node_3d: &Gd<Node3D>) -> Option<Rect2> {
+    let camera = node_3d.get_viewport()?.get_camera_3d()?;
+    let mut corners = Vec::new();
+    collect_world_corners(node_3d, &mut corners);
+    if corners.is_empty() {
+        corners.push(node_3d.get_global_position());
+    }
+    get_bounding_rect(corners.into_iter()
+        .filter(|corner| !camera.is_position_behind(*corner))
+        .map(|corner| camera.unproject_position(corner)))
+}
+
+fn collect_world_corners(node_3d: &Gd<Node3D>, corners: &mut Vec<Vector3>) {
+    let aabb = if let Ok(visual_instance) = node_3d.clone().try_cast::<VisualInstance3D>() {
+        Some(visual_instance.get_aabb())
+    } else if let Ok(collision_shape) = node_3d.clone().try_cast::<CollisionShape3D>() {
+        collision_shape.get_shape()
+            .and_then(|shape| shape.get_debug_mesh())
+            .map(|mesh| mesh.get_aabb())
+    } else {
+        None
+    };
+    if let Some(aabb) = aabb {
+        let transform = node_3d.get_global_transform();
+        for index in 0..8 {
+            let corner = aabb.position + Vector3::new(
+                if index & 1 == 0 { 0.0 } else { aabb.size.x },
+                if index & 2 == 0 { 0.0 } else { aabb.size.y },
+                if index & 4 == 0 { 0.0 } else { aabb.size.z },
+            );
+            corners.push(transform * corner);
+        }
+    }
+    for child in node_3d.get_children().iter_shared() {
+        if let Ok(child_3d) = child.try_cast::<Node3D>() {
+            if child_3d.is_visible() {
+                collect_world_corners(&child_3d, corners);
+            }
+        }
+    }
+}
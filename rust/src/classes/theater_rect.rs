@@ -1,8 +1,15 @@
 use godot::prelude::*;
-use godot::builtin::{Corner, Side};
-use godot::classes::{Control, IControl, Panel, Shader, ShaderMaterial, StyleBoxFlat, StyleBoxTexture, control, notify};
+use godot::builtin::Corner;
+use godot::classes::{
+    Control, IControl, InputEvent, InputEventAction, InputEventJoypadButton, InputEventJoypadMotion, InputEventKey,
+    InputEventMouseButton, Image, ImageTexture, Panel, Shader, ShaderMaterial, Shortcut, StyleBoxFlat,
+    Tween, control, image, notify,
+};
+use godot::classes::tween::{EaseType, TransitionType};
 
+use super::cutout_transition::{Cutout, CutoutTransition};
 use super::focused_node::FocusedNode;
+use super::target_rect;
 
 #[derive(GodotClass)]
 #[class(base = Control, tool)]
@@ -15,10 +22,57 @@ pub struct TheaterRect {
     #[export]
     pub background_color: Color,
     // Prevent mouse input outside of focused area.
+    // Key, joypad & action events are blocked unless a focused control has focus.
     #[export]
     pub confine_input: bool,
+    /// Actions allowed while input is confined.
+    #[export]
+    pub allowed_actions: Array<StringName>,
+    /// Shortcuts allowed while input is confined.
+    #[export]
+    pub allowed_shortcuts: Array<Option<Gd<Shortcut>>>,
+    /// Controls receiving input while input is confined, without a cutout.
+    #[export]
+    pub allowed_controls: Array<Option<Gd<Control>>>,
+    /// Copy focused nodes, settings & visibility from this TheaterRect.
+    /// Used to cover other windows than the one the followed TheaterRect is in.
+    #[var]
+    pub follow: Option<Gd<TheaterRect>>,
+    /// Duration of the cutouts moving between focused nodes. Disabled when 0.
+    #[export_group(name = "Transition")]
+    #[export(range = (0.0, 5.0, or_greater, suffix = "s"))]
+    pub transition_duration: f64,
+    #[export]
+    pub transition_type: TransitionType,
+    #[export]
+    pub transition_ease: EaseType,
+    /// Duration of fading in when shown & fading out when hidden. Disabled when 0.
+    #[export(range = (0.0, 5.0, or_greater, suffix = "s"))]
+    pub fade_duration: f64,
+    /// Briefly scale up the overlay nearest to a click outside of the focused nodes.
+    #[export_group(name = "Blocked Clicks")]
+    #[export]
+    pub pulse_on_blocked_click: bool,
+    #[export(range = (0.0, 2.0, or_greater, suffix = "s"))]
+    pub pulse_duration: f64,
     // Cached material.
     cutout_material: Gd<ShaderMaterial>,
+    // Cutouts passed to the shader, see `update_shader_params`.
+    cutout_texture: Option<Gd<ImageTexture>>,
+    // Cutouts in the cutout texture.
+    uploaded_cutouts: Vec<Cutout>,
+    // Cutouts drawn in the last frame.
+    displayed_cutouts: Vec<Cutout>,
+    // Focused nodes the displayed cutouts belong to.
+    displayed_focused_nodes: Vec<Option<InstanceId>>,
+    transition: Option<CutoutTransition>,
+    // Opacity of the background & overlays.
+    fade: f64,
+    fading_out: bool,
+    // Focused nodes when `focused_nodes_changed` was last emitted.
+    known_focused_nodes: Vec<Option<InstanceId>>,
+    // Focused nodes & the targets they were last resolved to, for `target_lost` & `target_resolved`.
+    target_states: Vec<(InstanceId, Option<InstanceId>)>,
 }
 
 #[godot_api]
@@ -34,7 +88,26 @@ impl IControl for TheaterRect {
             focused_nodes: Array::new(),
             background_color: Color::from_rgba(0.0, 0.0, 0.0, 0.9),
             confine_input: true,
+            allowed_actions: Array::new(),
+            allowed_shortcuts: Array::new(),
+            allowed_controls: Array::new(),
+            follow: None,
+            transition_duration: 0.3,
+            transition_type: TransitionType::CUBIC,
+            transition_ease: EaseType::IN_OUT,
+            fade_duration: 0.2,
+            pulse_on_blocked_click: true,
+            pulse_duration: 0.3,
             cutout_material: material,
+            cutout_texture: None,
+            uploaded_cutouts: Vec::new(),
+            displayed_cutouts: Vec::new(),
+            displayed_focused_nodes: Vec::new(),
+            transition: None,
+            fade: 1.0,
+            fading_out: false,
+            known_focused_nodes: Vec::new(),
+            target_states: Vec::new(),
         }
     }
 
@@ -43,36 +116,57 @@ impl IControl for TheaterRect {
         self.base_mut().set_material(&material_clone);
     }
 
-    fn process(&mut self, _delta: f64) {
-        let mut invalid = false;
-        if !self.base().is_visible_in_tree() {
-            invalid = true;
-        } else if self.focused_nodes.is_empty() {
-            self.base_mut().set_visible(false);
-            invalid = true;
+    fn process(&mut self, delta: f64) {
+        self.update_follow();
+        self.update_known_focused_nodes();
+        if self.base().is_visible_in_tree() && self.focused_nodes.is_empty() {
+            self.hide_animated();
         }
+        self.update_fade(delta);
 
-        if invalid {
+        if !self.base().is_visible_in_tree() {
             self.base_mut().set_mouse_filter(control::MouseFilter::IGNORE);
             return;
         }
 
-        self.update();
+        self.update_target_states();
+        self.update(delta);
 
         // Confine input to the focused control rect.
-        if self.confine_input {
-            if let Some(viewport) = self.base().get_viewport() {
-                if self.has_point(viewport.get_mouse_position()) {
-                    self.base_mut().set_mouse_filter(control::MouseFilter::IGNORE);
-                } else {
-                    self.base_mut().set_mouse_filter(control::MouseFilter::STOP);
-                }
+        if self.confine_input && !self.fading_out {
+            if self.has_point(self.base().get_global_mouse_position()) {
+                self.base_mut().set_mouse_filter(control::MouseFilter::IGNORE);
+            } else {
+                self.base_mut().set_mouse_filter(control::MouseFilter::STOP);
             }
         } else {
             self.base_mut().set_mouse_filter(control::MouseFilter::IGNORE);
         }
     }
 
+    fn input(&mut self, event: Gd<InputEvent>) {
+        if !self.is_confining_input() || !Self::is_confinable_event(&event) || self.is_event_allowed(&event) {
+            return;
+        }
+        if let Some(mut viewport) = self.base().get_viewport() {
+            viewport.set_input_as_handled();
+        }
+        self.base_mut().emit_signal("blocked_input", &[event.to_variant()]);
+    }
+
+    fn gui_input(&mut self, event: Gd<InputEvent>) {
+        // Only reached outside of the cutouts while input is confined, see `process`.
+        let is_press = event.clone().try_cast::<InputEventMouseButton>().is_ok_and(|mouse_button| mouse_button.is_pressed());
+        if is_press {
+            let position = self.base().get_global_mouse_position();
+            if self.pulse_on_blocked_click {
+                self.pulse_nearest_overlay(position);
+            }
+            self.base_mut().emit_signal("blocked_input", &[event.to_variant()]);
+            self.base_mut().emit_signal("blocked_click", &[position.to_variant()]);
+        }
+    }
+
     fn draw(&mut self) {
         let rect = self.base().get_rect();
         self.base_mut().draw_rect(rect, TheaterRect::DEFAULT_COLOR);
@@ -91,6 +185,20 @@ impl IControl for TheaterRect {
                 let material_clone = self.cutout_material.clone();
                 self.base_mut().set_material(&material_clone);
             },
+            notify::ControlNotification::VISIBILITY_CHANGED => {
+                if self.base().is_visible() {
+                    // Fade in & snap the cutouts to their targets.
+                    self.fade = if self.fade_duration > 0.0 { 0.0 } else { 1.0 };
+                    self.fading_out = false;
+                    self.transition = None;
+                    self.displayed_cutouts.clear();
+                    self.displayed_focused_nodes.clear();
+                    self.base_mut().emit_signal("shown", &[]);
+                } else {
+                    self.target_states.clear();
+                    self.base_mut().emit_signal("hidden", &[]);
+                }
+            },
             _ => {}
         }
     }
@@ -101,31 +209,268 @@ impl TheaterRect {
     // The color to use when drawing rect.
     // Chose transparent red so the user understands there's something wrong.
     const DEFAULT_COLOR: Color = Color::from_rgba(1.0, 0.0, 0.0, 0.75);
+    // Maximum cutouts stored in each row of the cutout texture.
+    const CUTOUTS_PER_ROW: usize = 256;
+    // Texels used by each cutout: rect, corner radii & shape.
+    const TEXELS_PER_CUTOUT: usize = 3;
+    // Scale of an overlay at the peak of a pulse.
+    const PULSE_SCALE: f32 = 1.08;
+
+    /// Emitted when shown, before fading in.
+    #[signal]
+    fn shown();
+
+    /// Emitted when hidden, after fading out.
+    #[signal]
+    fn hidden();
+
+    /// Emitted when focused nodes were added or removed.
+    #[signal]
+    fn focused_nodes_changed();
+
+    /// Emitted when the target of a focused node can't be found, e.g. because it was freed or a selector
+    /// matches nothing. Emitted again after the target was resolved & lost again.
+    #[signal]
+    fn target_lost(focused_node: Gd<FocusedNode>);
+
+    /// Emitted when the target of a focused node was found, or was replaced by another node.
+    #[signal]
+    fn target_resolved(focused_node: Gd<FocusedNode>, target: Gd<Node>);
+
+    /// Emitted when input outside of the focused nodes was blocked.
+    #[signal]
+    fn blocked_input(event: Gd<InputEvent>);
+
+    /// Emitted when a click outside of the focused nodes was blocked, after `blocked_input`.
+    #[signal]
+    fn blocked_click(position: Vector2);
+
+    /// Fade out, then hide.
+    #[func]
+    pub fn hide_animated(&mut self) {
+        if !self.base().is_visible() || self.fading_out {
+            return;
+        }
+        if self.fade_duration > 0.0 {
+            self.fading_out = true;
+        } else {
+            self.base_mut().set_visible(false);
+        }
+    }
 
-    fn update(&mut self) {
-        self.update_shader_params();
-        self.update_overlays();
+    /// Show & fade in. Cancels fading out.
+    #[func]
+    pub fn show_animated(&mut self) {
+        self.fading_out = false;
+        if !self.base().is_visible() {
+            self.base_mut().set_visible(true);
+        }
     }
 
-    fn update_shader_params(&mut self) {
-        let rects = self.get_rects();
-        let corners = self.get_corners();
-        self.cutout_material.set_shader_parameter("rects", &rects.to_variant());
-        self.cutout_material.set_shader_parameter("corners", &corners.to_variant());
-        self.cutout_material.set_shader_parameter("background_color", &self.background_color.to_variant());
+    fn update_follow(&mut self) {
+        let Some(follow) = self.follow.clone() else {
+            return;
+        };
+        if !follow.is_instance_valid() {
+            self.follow = None;
+            return;
+        }
+        let (visible, fading_out) = {
+            let follow = follow.bind();
+            // Share the array, so changes to the followed focused nodes apply immediately.
+            self.focused_nodes = follow.focused_nodes.clone();
+            self.background_color = follow.background_color;
+            self.confine_input = follow.confine_input;
+            self.allowed_actions = follow.allowed_actions.clone();
+            self.allowed_shortcuts = follow.allowed_shortcuts.clone();
+            self.allowed_controls = follow.allowed_controls.clone();
+            self.transition_duration = follow.transition_duration;
+            self.transition_type = follow.transition_type;
+            self.transition_ease = follow.transition_ease;
+            self.fade_duration = follow.fade_duration;
+            (follow.base().is_visible(), follow.fading_out)
+        };
+        if visible && !fading_out {
+            self.show_animated();
+        } else {
+            self.hide_animated();
+        }
     }
 
-    fn update_overlays(&self) {
-        self.focused_nodes.iter_shared().for_each(|focused_node_result| {
+    fn update_known_focused_nodes(&mut self) {
+        let focused_nodes = self.get_focused_node_ids();
+        if focused_nodes != self.known_focused_nodes {
+            self.known_focused_nodes = focused_nodes;
+            self.base_mut().emit_signal("focused_nodes_changed", &[]);
+        }
+    }
+
+    fn update_target_states(&mut self) {
+        let mut target_states = Vec::new();
+        let mut lost = Vec::new();
+        let mut resolved = Vec::new();
+        for focused_node in self.focused_nodes.iter_shared().flatten() {
+            let target = self.get_target(&focused_node);
+            let target_id = target.as_ref().map(|target| target.instance_id());
+            // None for focused nodes that weren't tracked yet.
+            let previous_target_id = self.target_states.iter()
+                .find(|(focused_node_id, _)| *focused_node_id == focused_node.instance_id())
+                .map(|(_, target_id)| *target_id);
+            match target {
+                None if previous_target_id != Some(None) => lost.push(focused_node.clone()),
+                Some(target) if previous_target_id != Some(target_id) => resolved.push((focused_node.clone(), target)),
+                _ => {}
+            }
+            target_states.push((focused_node.instance_id(), target_id));
+        }
+        self.target_states = target_states;
+        for focused_node in lost {
+            self.base_mut().emit_signal("target_lost", &[focused_node.to_variant()]);
+        }
+        for (focused_node, target) in resolved {
+            self.base_mut().emit_signal("target_resolved", &[focused_node.to_variant(), target.to_variant()]);
+        }
+    }
+
+    fn get_focused_node_ids(&self) -> Vec<Option<InstanceId>> {
+        self.focused_nodes.iter_shared()
+            .map(|focused_node_result| focused_node_result.map(|focused_node| focused_node.instance_id()))
+            .collect()
+    }
+
+    fn update(&mut self, delta: f64) {
+        let cutouts = self.get_cutouts();
+        let (displayed_cutouts, removed_cutouts) = self.update_transition(&cutouts, delta);
+        let shader_cutouts: Vec<Cutout> = displayed_cutouts.iter().chain(removed_cutouts.iter()).copied().collect();
+        self.update_shader_params(&shader_cutouts);
+        self.update_overlays(&cutouts, &displayed_cutouts);
+        self.displayed_cutouts = displayed_cutouts;
+    }
+
+    fn update_fade(&mut self, delta: f64) {
+        let step = if self.fade_duration > 0.0 { delta / self.fade_duration } else { 1.0 };
+        if self.fading_out {
+            self.fade = (self.fade - step).max(0.0);
+            if self.fade <= 0.0 {
+                self.fading_out = false;
+                self.base_mut().set_visible(false);
+            }
+        } else if self.fade < 1.0 {
+            self.fade = (self.fade + step).min(1.0);
+        }
+    }
+
+    /// Start a transition when the focused nodes changed and advance the running transition.
+    /// Returns the cutouts to draw for each focused node and the cutouts shrinking away.
+    fn update_transition(&mut self, cutouts: &[Cutout], delta: f64) -> (Vec<Cutout>, Vec<Cutout>) {
+        let focused_nodes = self.get_focused_node_ids();
+        if focused_nodes != self.displayed_focused_nodes {
+            if self.transition_duration > 0.0 && !self.displayed_cutouts.is_empty() {
+                self.transition = Some(CutoutTransition::new(&self.displayed_cutouts, cutouts, self.transition_duration));
+            }
+            self.displayed_focused_nodes = focused_nodes;
+        }
+
+        let Some(transition) = self.transition.as_mut() else {
+            return (cutouts.to_vec(), Vec::new());
+        };
+        transition.advance(delta);
+        let progress = transition.progress();
+        let weight = Tween::interpolate_value(
+            &0.0.to_variant(),
+            &1.0.to_variant(),
+            progress,
+            1.0,
+            self.transition_type,
+            self.transition_ease,
+        ).try_to::<f64>().unwrap_or(progress) as f32;
+        let result = transition.interpolate(cutouts, weight);
+        if transition.is_finished() {
+            self.transition = None;
+        }
+        result
+    }
+
+    /// Pass the cutouts to the shader as a float texture, so there's no limit on the number of cutouts
+    /// and the shader only loops over the cutouts in use. The texture is only uploaded when the cutouts changed.
+    fn update_shader_params(&mut self, cutouts: &[Cutout]) {
+        if self.cutout_texture.is_none() || self.uploaded_cutouts != cutouts {
+            self.upload_cutouts(cutouts);
+        }
+        let mut background_color = self.background_color;
+        background_color.a *= self.fade as f32;
+        self.cutout_material.set_shader_parameter("background_color", &background_color.to_variant());
+    }
+
+    fn upload_cutouts(&mut self, cutouts: &[Cutout]) {
+        // Rows grow in powers of two up to CUTOUTS_PER_ROW, so a few cutouts use a small texture.
+        let cutouts_per_row = cutouts.len().max(1).next_power_of_two().min(Self::CUTOUTS_PER_ROW);
+        let rows = cutouts.len().div_ceil(cutouts_per_row).max(1);
+        let width = cutouts_per_row * Self::TEXELS_PER_CUTOUT;
+        let mut data: Vec<f32> = vec![0.0; width * rows * 4];
+        for (index, cutout) in cutouts.iter().enumerate() {
+            let row = index / cutouts_per_row;
+            let column = (index % cutouts_per_row) * Self::TEXELS_PER_CUTOUT;
+            let offset = (row * width + column) * 4;
+            data[offset..offset + 12].copy_from_slice(&[
+                cutout.rect.position.x, cutout.rect.position.y, cutout.rect.size.x, cutout.rect.size.y,
+                cutout.corner_radii.x, cutout.corner_radii.y, cutout.corner_radii.z, cutout.corner_radii.w,
+                cutout.shape as i32 as f32, 0.0, 0.0, 0.0,
+            ]);
+        }
+        let bytes: Vec<u8> = data.iter().flat_map(|value| value.to_le_bytes()).collect();
+        let Some(image) = Image::create_from_data(
+            width as i32,
+            rows as i32,
+            false,
+            image::Format::RGBAF,
+            &PackedByteArray::from(bytes.as_slice()),
+        ) else {
+            return;
+        };
+
+        // Reuse the texture while its size doesn't change.
+        match self.cutout_texture.as_mut() {
+            Some(texture) if texture.get_width() == width as i32 && texture.get_height() == rows as i32 => {
+                texture.update(&image);
+            }
+            _ => {
+                self.cutout_texture = ImageTexture::create_from_image(&image);
+                let texture = self.cutout_texture.clone();
+                self.cutout_material.set_shader_parameter("cutout_data", &texture.to_variant());
+                self.cutout_material.set_shader_parameter("cutouts_per_row", &(cutouts_per_row as i32).to_variant());
+            }
+        }
+        self.cutout_material.set_shader_parameter("cutout_count", &(cutouts.len() as i32).to_variant());
+        self.uploaded_cutouts = cutouts.to_vec();
+    }
+
+    /// Overlays follow the displayed cutouts, offset from their targets while transitioning.
+    /// Overlays in other windows are left to the TheaterRect of that window.
+    fn update_overlays(&self, cutouts: &[Cutout], displayed_cutouts: &[Cutout]) {
+        let native_window = target_rect::get_native_window(&self.base().clone().upcast());
+        self.focused_nodes.iter_shared().enumerate().for_each(|(index, focused_node_result)| {
             if let Some(focused_node) = focused_node_result {
                 let overlay_nodepath = focused_node.bind().overlay.clone();
                 if let Some(mut overlay) = self.base().try_get_node_as::<Panel>(&overlay_nodepath) {
-                    let target_nodepath = focused_node.bind().target.clone();
-                    if let Some(target) = self.base().try_get_node_as::<Control>(&target_nodepath) {
-                        overlay.set_visible(target.is_visible_in_tree());
-                        let rect = target.get_global_rect().grow(1.0);
+                    if target_rect::get_native_window(&overlay.clone().upcast()) != native_window {
+                        return;
+                    }
+                    let target_rect = self.get_target(&focused_node)
+                        .filter(target_rect::is_target_visible)
+                        .and_then(|target| self.get_target_rect(&target));
+                    if let Some(mut rect) = target_rect {
+                        overlay.set_visible(true);
+                        if let (Some(cutout), Some(displayed_cutout)) = (cutouts.get(index), displayed_cutouts.get(index)) {
+                            rect.position += displayed_cutout.rect.position - cutout.rect.position;
+                            rect.size += displayed_cutout.rect.size - cutout.rect.size;
+                        }
+                        let rect = rect.grow(1.0);
                         overlay.set_position(rect.position);
                         overlay.set_size(rect.size);
+                        let mut modulate = overlay.get_modulate();
+                        modulate.a = self.fade as f32;
+                        overlay.set_modulate(modulate);
                     } else {
                         overlay.set_visible(false);
                     }
@@ -134,6 +479,30 @@ impl TheaterRect {
         });
     }
 
+    /// Scale the visible overlay closest to `position` up & back down.
+    fn pulse_nearest_overlay(&mut self, position: Vector2) {
+        let nearest = self.focused_nodes.iter_shared().flatten()
+            .filter_map(|focused_node| self.base().try_get_node_as::<Panel>(&focused_node.bind().overlay.clone()))
+            .filter(|overlay| overlay.is_visible_in_tree())
+            .map(|overlay| {
+                let rect = overlay.get_global_rect();
+                let closest = position.clamp(rect.position, rect.end());
+                (closest.distance_squared_to(position), overlay)
+            })
+            .min_by(|(a, _), (b, _)| a.partial_cmp(b).unwrap_or(std::cmp::Ordering::Equal))
+            .map(|(_, overlay)| overlay);
+        let Some(mut overlay) = nearest else {
+            return;
+        };
+        let Some(mut tween) = self.base_mut().create_tween() else {
+            return;
+        };
+        overlay.set_pivot_offset(overlay.get_size() / 2.0);
+        let half_duration = self.pulse_duration / 2.0;
+        tween.tween_property(&overlay, "scale", &Vector2::splat(Self::PULSE_SCALE).to_variant(), half_duration);
+        tween.tween_property(&overlay, "scale", &Vector2::ONE.to_variant(), half_duration);
+    }
+
     fn reset_overlays(&self) {
         self.focused_nodes.iter_shared().for_each(|focused_node_result| {
             if let Some(focused_node) = focused_node_result {
@@ -141,6 +510,10 @@ impl TheaterRect {
                 if let Some(mut overlay) = self.base().try_get_node_as::<Panel>(&overlay_nodepath) {
                     overlay.set_position(Vector2::default());
                     overlay.set_size(Vector2::default());
+                    overlay.set_scale(Vector2::ONE);
+                    let mut modulate = overlay.get_modulate();
+                    modulate.a = 1.0;
+                    overlay.set_modulate(modulate);
                 }
             }
         });
@@ -148,65 +521,165 @@ impl TheaterRect {
 
     fn get_rects(&self) -> Array<Rect2> {
         self.focused_nodes.iter_shared().map(|focused_node_result|{
-            if let Some(focused_node) = focused_node_result {
-                if let Some(target) = self.base().try_get_node_as::<Control>(&focused_node.bind().target.clone()) {
-                    if target.is_visible_in_tree() {
-                        let target_rect = target.get_global_rect();
-                        let overlay_nodepath = focused_node.bind().overlay.clone();
-                        if let Some(overlay) = self.base().try_get_node_as::<Panel>(&overlay_nodepath) {
-                            if let Some(stylebox) = overlay.get_theme_stylebox("panel") {
-                                if let Ok(flat_stylebox) = stylebox.clone().try_cast::<StyleBoxFlat>() {
-                                    return target_rect.grow_individual(
-                                        flat_stylebox.get_expand_margin(Side::LEFT),
-                                        flat_stylebox.get_expand_margin(Side::TOP),
-                                        flat_stylebox.get_expand_margin(Side::RIGHT),
-                                        flat_stylebox.get_expand_margin(Side::BOTTOM),
-                                    );
-                                } else if let Ok(texture_stylebox) = stylebox.try_cast::<StyleBoxTexture>() {
-                                    return target_rect.grow_individual(
-                                        texture_stylebox.get_expand_margin(Side::LEFT),
-                                        texture_stylebox.get_expand_margin(Side::TOP),
-                                        texture_stylebox.get_expand_margin(Side::RIGHT),
-                                        texture_stylebox.get_expand_margin(Side::BOTTOM),
-                                    );
-                                }
-                            }
-                        }
-                        return target_rect;
-                    }
-                }
+            focused_node_result
+                .and_then(|focused_node| self.get_focused_node_rect(&focused_node))
+                .unwrap_or_default()
+        }).collect()
+    }
+
+    /// Rect of the cutout of a focused node, grown by the expand margins of its overlay.
+    /// None if the target can't be found or isn't visible.
+    pub fn get_focused_node_rect(&self, focused_node: &Gd<FocusedNode>) -> Option<Rect2> {
+        let target = self.get_target(focused_node)?;
+        if !target_rect::is_target_visible(&target) {
+            return None;
+        }
+        let target_rect = self.get_target_rect(&target)?;
+        let overlay_nodepath = focused_node.bind().overlay.clone();
+        let stylebox = self.base().try_get_node_as::<Panel>(&overlay_nodepath)
+            .and_then(|overlay| overlay.get_theme_stylebox("panel"));
+        match stylebox {
+            Some(stylebox) => Some(target_rect::grow_by_expand_margins(target_rect, &stylebox)),
+            None => Some(target_rect),
+        }
+    }
+
+    /// Rects of the cutouts while shown, grown by the expand margins of their overlays.
+    pub fn get_cutout_rects(&self) -> Vec<Rect2> {
+        if !self.base().is_visible_in_tree() {
+            return Vec::new();
+        }
+        self.get_rects().iter_shared().filter(|rect| rect.area() > 0.0).collect()
+    }
+
+    /// Cutout rect of the first focused node targeting `target`.
+    pub fn get_cutout_rect_of(&self, target: &Gd<Node>) -> Option<Rect2> {
+        self.focused_nodes.iter_shared().flatten().find_map(|focused_node| {
+            if self.get_target(&focused_node).as_ref() != Some(target) {
+                return None;
             }
-            Rect2::default()
+            self.get_focused_node_rect(&focused_node)
+        })
+    }
+
+    fn get_cutouts(&self) -> Vec<Cutout> {
+        let corners = self.get_corners();
+        let shapes = self.focused_nodes.iter_shared().map(|focused_node_result| {
+            focused_node_result.map(|focused_node| focused_node.bind().shape).unwrap_or_default()
+        });
+        self.get_rects().iter_shared().zip(corners.iter_shared()).zip(shapes).map(|((rect, corner_radii), shape)| {
+            Cutout::new(rect, corner_radii, shape)
         }).collect()
     }
 
-    fn get_corners(&self) -> Array<f32> {
+    fn get_corners(&self) -> Array<Vector4> {
         self.focused_nodes.iter_shared().map(|focused_node_result|{
             if let Some(focused_node) = focused_node_result {
                 let overlay_nodepath = focused_node.bind().overlay.clone();
                 if let Some(overlay) = self.base().try_get_node_as::<Panel>(&overlay_nodepath) {
                     if let Some(stylebox) = overlay.get_theme_stylebox("panel") {
                         if let Ok(stylebox_flat) = stylebox.try_cast::<StyleBoxFlat>() {
-                            return stylebox_flat.get_corner_radius(Corner::TOP_LEFT) as f32;
+                            return Vector4::new(
+                                stylebox_flat.get_corner_radius(Corner::TOP_LEFT) as f32,
+                                stylebox_flat.get_corner_radius(Corner::TOP_RIGHT) as f32,
+                                stylebox_flat.get_corner_radius(Corner::BOTTOM_RIGHT) as f32,
+                                stylebox_flat.get_corner_radius(Corner::BOTTOM_LEFT) as f32,
+                            );
                         }
                     }
                 }
             }
-            0.0
+            Vector4::ZERO
         }).collect()
     }
 
-    fn has_point(&self, point: Vector2) -> bool {
-        for focused_node_result in self.focused_nodes.iter_shared() {
-            if let Some(focused_node) = focused_node_result {
-                if let Some(target) = self.base().try_get_node_as::<Control>(&focused_node.bind().target.clone()) {
-                    let target_rect = target.get_global_rect();
-                    if target_rect.contains_point(point) {
-                        return true;
-                    } 
-                }
+    /// Whether `control` is a focused target, lies inside one or inside an allowed control.
+    pub fn is_in_focused_region(&self, control: &Gd<Control>) -> bool {
+        let node = control.clone().upcast::<Node>();
+        let control_rect = self.get_target_rect(&node);
+        let is_allowed = self.allowed_controls.iter_shared().flatten().any(|allowed_control| {
+            allowed_control == *control || allowed_control.is_ancestor_of(&node)
+        });
+        if is_allowed {
+            return true;
+        }
+        self.focused_nodes.iter_shared().flatten().any(|focused_node| {
+            let Some(target) = self.get_target(&focused_node) else {
+                return false;
+            };
+            if target == node {
+                return true;
             }
+            match (self.get_target_rect(&target), control_rect) {
+                (Some(target_rect), Some(control_rect)) => target_rect.encloses(control_rect),
+                _ => false,
+            }
+        })
+    }
+
+    /// Whether input outside of the focused nodes is currently blocked.
+    pub fn is_confining_input(&self) -> bool {
+        self.confine_input
+            && !self.fading_out
+            && !self.focused_nodes.is_empty()
+            && self.base().is_visible_in_tree()
+            && !self.is_in_edited_scene()
+    }
+
+    // A TheaterRect in the scene being edited mustn't block editor input.
+    fn is_in_edited_scene(&self) -> bool {
+        let Some(tree) = self.base().get_tree() else {
+            return false;
+        };
+        let Some(edited_scene_root) = tree.get_edited_scene_root() else {
+            return false;
+        };
+        let node = self.base().clone().upcast::<Node>();
+        edited_scene_root == node || edited_scene_root.is_ancestor_of(&node)
+    }
+
+    fn is_confinable_event(event: &Gd<InputEvent>) -> bool {
+        event.clone().try_cast::<InputEventKey>().is_ok()
+            || event.clone().try_cast::<InputEventJoypadButton>().is_ok()
+            || event.clone().try_cast::<InputEventJoypadMotion>().is_ok()
+            || event.clone().try_cast::<InputEventAction>().is_ok()
+    }
+
+    /// Allowed actions & shortcuts pass, as does input to a focused control with focus.
+    fn is_event_allowed(&self, event: &Gd<InputEvent>) -> bool {
+        if self.allowed_actions.iter_shared().any(|action| event.is_action(&action)) {
+            return true;
+        }
+        if self.allowed_shortcuts.iter_shared().flatten().any(|shortcut| shortcut.matches_event(event)) {
+            return true;
         }
-        return false;
+        let focus_owner = self.base().get_viewport().and_then(|viewport| viewport.gui_get_focus_owner());
+        match focus_owner {
+            Some(focus_owner) => self.is_in_focused_region(&focus_owner),
+            None => false,
+        }
+    }
+
+    /// Target of a focused node, `target` paths are resolved relative to this node.
+    pub fn get_target(&self, focused_node: &Gd<FocusedNode>) -> Option<Gd<Node>> {
+        let relative_to = self.base().clone().upcast::<Node>();
+        focused_node.clone().bind_mut().resolve(&relative_to)
+    }
+
+    /// Rect of a target in the canvas coordinates of this node.
+    /// None if the target isn't displayed in the viewport of this node.
+    pub fn get_target_rect(&self, target: &Gd<Node>) -> Option<Rect2> {
+        let viewport = self.base().get_viewport()?;
+        let viewport_rect = target_rect::get_rect_in_viewport(target, &viewport)?;
+        let canvas_transform = self.base().get_canvas_transform().affine_inverse();
+        Some(target_rect::transform_rect(canvas_transform, viewport_rect))
+    }
+
+    fn has_point(&self, point: Vector2) -> bool {
+        self.displayed_cutouts.iter().any(|cutout| cutout.contains_point(point))
+            || self.allowed_controls.iter_shared().flatten().any(|control| {
+                control.is_visible_in_tree()
+                    && self.get_target_rect(&control.upcast()).is_some_and(|rect| rect.contains_point(point))
+            })
     }
 }
@@ -0,0 +1,485 @@
+use godot::prelude::*;
+use godot::builtin::{NodePath, Side};
+use godot::classes::{
+    Button, Control, FileAccess, HBoxContainer, Json, Label, Panel, PanelContainer, StyleBox, StyleBoxFlat,
+    VBoxContainer,
+};
+use godot::classes::control::{MouseFilter, SizeFlags};
+use godot::classes::text_server::AutowrapMode;
+
+use super::focused_node::CutoutShape;
+use super::popup_container::{PopupAnchor, PopupContainer, PopupPosition};
+use super::selector::Selector;
+use super::target_rect;
+use super::theater_rect::TheaterRect;
+use super::tour_singleton::TourSingleton;
+use super::tour_step::TourStep;
+
+/// Result of loading a tour definition.
+pub struct LoadedTour {
+    pub id: GString,
+    pub steps: Array<Option<Gd<TourStep>>>,
+    /// Overlays & popups created for the tour, owned by the TheaterRect of their window.
+    pub nodes: Vec<Gd<Node>>,
+}
+
+/// Builds tour steps from a tour definition.
+///
+/// A tour definition is a JSON object:
+/// ```json
+/// {
+///     "id": "editor_basics",
+///     "overlay": { "border_color": "#478cbf", "border_width": 2, "corner_radius": 4, "expand_margin": 4 },
+///     "steps": [{
+///         "title": "Run Bar",
+///         "text": "Use these buttons to run your project.",
+///         "targets": [{ "editor": "run_bar" }, { "path": "/root/Main/Button", "overlay": "res://Overlay.stylebox", "shape": "circle" },
+///             { "selector": "SceneTreeDock Button[tooltip_text^=\"Add\"]" }],
+///         "popup": { "target": 0, "position": "bottom_center", "smart_position": true, "arrow": true, "gap": 4,
+///             "fallback": ["top_center", "right_center"], "min_width": 250 },
+///         "advance": { "signal": "pressed", "target": 0, "timeout": 0 },
+///         "allowed_actions": ["ui_accept"],
+///         "metadata": {}
+///     }]
+/// }
+/// ```
+/// Target overlays default to the top level `overlay`. An overlay of `false` disables it.
+/// A popup `anchor` of `"point"`, `"rect"`, `"mouse"`, `"group"` or `"focused_node"` places the popup next to
+/// `"point": [x, y]`, `"rect": [x, y, width, height]`, the mouse, the union of `"group": [0, 1]` or the
+/// cutout of `target` instead.
+pub struct TourLoader<'a> {
+    tour: &'a mut TourSingleton,
+    default_overlay: Option<Variant>,
+    nodes: Vec<Gd<Node>>,
+}
+
+impl<'a> TourLoader<'a> {
+    pub fn new(tour: &'a mut TourSingleton) -> Self {
+        Self {
+            tour,
+            default_overlay: None,
+            nodes: Vec::new(),
+        }
+    }
+
+    /// Load a tour definition from a JSON file.
+    pub fn load_file(self, path: &str) -> Result<LoadedTour, String> {
+        if !FileAccess::file_exists(path) {
+            return Err(format!("file \"{path}\" doesn't exist"));
+        }
+        let text = FileAccess::get_file_as_string(path);
+        let mut json = Json::new_gd();
+        if json.parse(&text) != godot::global::Error::OK {
+            return Err(format!(
+                "\"{path}\" line {}: {}",
+                json.get_error_line(),
+                json.get_error_message()
+            ));
+        }
+        let Ok(definition) = json.get_data().try_to::<Dictionary>() else {
+            return Err(format!("\"{path}\" must contain a JSON object"));
+        };
+        self.load_dictionary(&definition)
+    }
+
+    /// Load a tour definition from an already parsed dictionary.
+    pub fn load_dictionary(mut self, definition: &Dictionary) -> Result<LoadedTour, String> {
+        let id = get_string(definition, "id").unwrap_or_default();
+        self.default_overlay = get(definition, "overlay");
+        let Some(step_definitions) = get(definition, "steps").and_then(|steps| steps.try_to::<Array<Variant>>().ok()) else {
+            return Err("\"steps\" must be an array".to_string());
+        };
+
+        let step_count = step_definitions.len();
+        let mut steps = Array::new();
+        for (index, step_definition) in step_definitions.iter_shared().enumerate() {
+            let Ok(step_definition) = step_definition.try_to::<Dictionary>() else {
+                self.free_nodes();
+                return Err(format!("step {index} must be an object"));
+            };
+            match self.build_step(&step_definition, index, step_count) {
+                Ok(step) => steps.push(Some(&step)),
+                Err(error) => {
+                    self.free_nodes();
+                    return Err(format!("step {index}: {error}"));
+                }
+            }
+        }
+
+        Ok(LoadedTour {
+            id: GString::from(id.as_str()),
+            steps,
+            nodes: self.nodes,
+        })
+    }
+
+    fn build_step(&mut self, definition: &Dictionary, index: usize, step_count: usize) -> Result<Gd<TourStep>, String> {
+        let mut step = TourStep::new_gd();
+        let title = get_string(definition, "title").unwrap_or_default();
+        let text = get_string(definition, "text").unwrap_or_default();
+
+        let mut targets: Vec<Gd<Node>> = Vec::new();
+        if let Some(target_definitions) = get(definition, "targets") {
+            let Ok(target_definitions) = target_definitions.try_to::<Array<Variant>>() else {
+                return Err("\"targets\" must be an array".to_string());
+            };
+            for target_definition in target_definitions.iter_shared() {
+                let Ok(target_definition) = target_definition.try_to::<Dictionary>() else {
+                    return Err("targets must be objects".to_string());
+                };
+                let target = self.resolve_target(&target_definition)?;
+                let overlay_definition = get(&target_definition, "overlay").or_else(|| self.default_overlay.clone());
+                let overlay = self.build_overlay(overlay_definition, &target)?;
+                let mut focused_node = TourSingleton::create_focused_node(Some(target.clone()), overlay);
+                if let Some(selector) = get_string(&target_definition, "selector") {
+                    focused_node.bind_mut().selector = GString::from(selector.as_str());
+                }
+                if let Some(shape) = get_string(&target_definition, "shape") {
+                    focused_node.bind_mut().shape = CutoutShape::from_name(&shape)
+                        .ok_or_else(|| format!("unknown shape \"{shape}\""))?;
+                }
+                step.bind_mut().focused_nodes.push(Some(&focused_node));
+                targets.push(target);
+            }
+        }
+
+        if let Some(popup_definition) = get(definition, "popup") {
+            let Ok(popup_definition) = popup_definition.try_to::<Dictionary>() else {
+                return Err("\"popup\" must be an object".to_string());
+            };
+            let mut popup = self.build_popup(&popup_definition, &targets, &title, &text, index, step_count)?;
+            if popup.bind().anchor == PopupAnchor::FocusedNode {
+                let target_index = get_number(&popup_definition, "target").unwrap_or(0.0) as usize;
+                let focused_node = step.bind().focused_nodes.get(target_index).flatten()
+                    .ok_or_else(|| format!("popup target {target_index} is out of range"))?;
+                popup.bind_mut().anchor_focused_node = Some(focused_node);
+            }
+            step.bind_mut().popup = popup.get_path();
+        }
+
+        if let Some(advance_definition) = get(definition, "advance") {
+            let Ok(advance_definition) = advance_definition.try_to::<Dictionary>() else {
+                return Err("\"advance\" must be an object".to_string());
+            };
+            if let Some(signal) = get_string(&advance_definition, "signal") {
+                step.bind_mut().advance_signal = StringName::from(signal.as_str());
+            }
+            if let Some(target_index) = get_number(&advance_definition, "target") {
+                let target = targets.get(target_index as usize)
+                    .ok_or_else(|| format!("advance target {target_index} is out of range"))?;
+                step.bind_mut().advance_node = target.get_path();
+            }
+            if let Some(timeout) = get_number(&advance_definition, "timeout") {
+                step.bind_mut().advance_timeout = timeout;
+            }
+        }
+
+        if let Some(allowed_actions) = get(definition, "allowed_actions") {
+            let Ok(allowed_actions) = allowed_actions.try_to::<Array<Variant>>() else {
+                return Err("\"allowed_actions\" must be an array".to_string());
+            };
+            for action in allowed_actions.iter_shared() {
+                let Ok(action) = action.try_to::<GString>() else {
+                    return Err("allowed actions must be strings".to_string());
+                };
+                step.bind_mut().allowed_actions.push(&StringName::from(&action));
+            }
+        }
+
+        if let Some(metadata) = get(definition, "metadata") {
+            let Ok(metadata) = metadata.try_to::<Dictionary>() else {
+                return Err("\"metadata\" must be an object".to_string());
+            };
+            step.bind_mut().metadata = metadata;
+        }
+        step.bind_mut().title = GString::from(title.as_str());
+        step.bind_mut().text = GString::from(text.as_str());
+        Ok(step)
+    }
+
+    fn resolve_target(&self, definition: &Dictionary) -> Result<Gd<Node>, String> {
+        if let Some(name) = get_string(definition, "editor") {
+            return self.tour.get_editor_control(GString::from(name.as_str()))
+                .map(|control| control.upcast())
+                .ok_or_else(|| format!("editor control \"{name}\" not found"));
+        }
+        if let Some(path) = get_string(definition, "path") {
+            return self.tour.theater_rect.try_get_node_as::<Node>(&NodePath::from(path.as_str()))
+                .ok_or_else(|| format!("no node at \"{path}\""));
+        }
+        if let Some(source) = get_string(definition, "selector") {
+            let selector = Selector::parse(&source)?;
+            let root = self.tour.theater_rect.get_tree()
+                .and_then(|tree| tree.get_root())
+                .ok_or_else(|| "selector targets need the theater_rect in the tree".to_string())?;
+            return selector.query(&root.upcast())
+                .ok_or_else(|| format!("no node matches \"{source}\""));
+        }
+        Err("target needs an \"editor\", \"path\" or \"selector\" key".to_string())
+    }
+
+    /// Build the overlay of `target`, added to the TheaterRect covering the window of `target`.
+    fn build_overlay(&mut self, definition: Option<Variant>, target: &Gd<Node>) -> Result<Option<Gd<Panel>>, String> {
+        let stylebox: Option<Gd<StyleBox>> = match definition {
+            None => None,
+            Some(definition) => {
+                if let Ok(enabled) = definition.try_to::<bool>() {
+                    if !enabled {
+                        return Ok(None);
+                    }
+                    None
+                } else if let Ok(path) = definition.try_to::<GString>() {
+                    let stylebox = try_load::<StyleBox>(&path.to_string())
+                        .map_err(|_| format!("couldn't load stylebox \"{path}\""))?;
+                    Some(stylebox)
+                } else if let Ok(style) = definition.try_to::<Dictionary>() {
+                    Some(build_stylebox(&style)?.upcast())
+                } else {
+                    return Err("\"overlay\" must be a bool, stylebox path or object".to_string());
+                }
+            }
+        };
+
+        let mut overlay = Panel::new_alloc();
+        overlay.set_mouse_filter(MouseFilter::IGNORE);
+        if let Some(stylebox) = stylebox {
+            overlay.add_theme_stylebox_override("panel", &stylebox);
+        }
+        let theater_rect = match target_rect::get_native_window(target) {
+            Some(window) => self.tour.get_theater_rect_for_window(window),
+            None => self.tour.theater_rect.clone(),
+        };
+        self.add_node(theater_rect, overlay.clone().upcast());
+        Ok(Some(overlay))
+    }
+
+    fn build_popup(
+        &mut self,
+        definition: &Dictionary,
+        targets: &[Gd<Node>],
+        title: &str,
+        text: &str,
+        index: usize,
+        step_count: usize,
+    ) -> Result<Gd<PopupContainer>, String> {
+        let mut popup = PopupContainer::new_alloc();
+        popup.set_visible(false);
+
+        if let Err(error) = configure_popup(&mut popup, definition, targets) {
+            // Not owned by the theater_rect yet.
+            popup.queue_free();
+            return Err(error);
+        }
+
+        let mut panel = PanelContainer::new_alloc();
+        let min_width = get(definition, "min_width")
+            .and_then(|value| value.try_to::<f64>().ok())
+            .unwrap_or(250.0) as f32;
+        panel.set_custom_minimum_size(Vector2::new(min_width, 0.0));
+        let mut content = VBoxContainer::new_alloc();
+        panel.add_child(&content);
+
+        if !title.is_empty() {
+            let mut title_label = Label::new_alloc();
+            title_label.set_text(title);
+            title_label.set_theme_type_variation("HeaderSmall");
+            content.add_child(&title_label);
+        }
+        if !text.is_empty() {
+            let mut text_label = Label::new_alloc();
+            text_label.set_text(text);
+            text_label.set_autowrap_mode(AutowrapMode::WORD_SMART);
+            content.add_child(&text_label);
+        }
+
+        let mut buttons = HBoxContainer::new_alloc();
+        let tour = self.tour.to_gd();
+        let mut close_button = Button::new_alloc();
+        close_button.set_text("Close");
+        close_button.connect("pressed", &tour.callable("cancel"));
+        buttons.add_child(&close_button);
+        let mut filler = Control::new_alloc();
+        filler.set_h_size_flags(SizeFlags::EXPAND_FILL);
+        buttons.add_child(&filler);
+        if index > 0 {
+            let mut previous_button = Button::new_alloc();
+            previous_button.set_text("Back");
+            previous_button.connect("pressed", &tour.callable("previous"));
+            buttons.add_child(&previous_button);
+        }
+        let mut next_button = Button::new_alloc();
+        next_button.set_text(if index + 1 >= step_count { "Done" } else { "Next" });
+        next_button.connect("pressed", &tour.callable("next"));
+        buttons.add_child(&next_button);
+        content.add_child(&buttons);
+
+        popup.add_child(&panel);
+        let theater_rect = self.tour.theater_rect.clone();
+        self.add_node(theater_rect, popup.clone().upcast());
+        Ok(popup)
+    }
+
+    fn add_node(&mut self, mut theater_rect: Gd<TheaterRect>, node: Gd<Node>) {
+        theater_rect.add_child(&node);
+        self.nodes.push(node);
+    }
+
+    fn free_nodes(&mut self) {
+        for mut node in self.nodes.drain(..) {
+            node.queue_free();
+        }
+    }
+}
+
+/// Apply the settings of a popup definition to `popup`.
+fn configure_popup(popup: &mut Gd<PopupContainer>, definition: &Dictionary, targets: &[Gd<Node>]) -> Result<(), String> {
+    let target_index = get(definition, "target")
+        .and_then(|target| target.try_to::<f64>().ok())
+        .unwrap_or(0.0) as usize;
+    if let Some(target) = targets.get(target_index) {
+        popup.bind_mut().target = target.get_path();
+    } else if !targets.is_empty() {
+        return Err(format!("popup target {target_index} is out of range"));
+    }
+    if let Some(position) = get_string(definition, "position") {
+        popup.bind_mut().position = PopupPosition::from_name(&position)
+            .ok_or_else(|| format!("unknown popup position \"{position}\""))?;
+    }
+    if let Some(smart_position) = get_bool(definition, "smart_position") {
+        popup.bind_mut().smart_position = smart_position;
+    }
+    if let Some(show_arrow) = get_bool(definition, "arrow") {
+        popup.bind_mut().show_arrow = show_arrow;
+    }
+    if let Some(gap) = get_number(definition, "gap") {
+        let mut popup = popup.bind_mut();
+        popup.gap_left = gap as f32;
+        popup.gap_top = gap as f32;
+        popup.gap_right = gap as f32;
+        popup.gap_bottom = gap as f32;
+    }
+    if let Some(viewport_margin) = get_number(definition, "viewport_margin") {
+        popup.bind_mut().viewport_margin = viewport_margin as f32;
+    }
+    if let Some(use_cutout_rect) = get_bool(definition, "use_cutout_rect") {
+        popup.bind_mut().use_cutout_rect = use_cutout_rect;
+    }
+    if let Some(flip) = get_bool(definition, "flip") {
+        popup.bind_mut().flip = flip;
+    }
+    if let Some(shift) = get_bool(definition, "shift") {
+        popup.bind_mut().shift = shift;
+    }
+    if let Some(clamp) = get_bool(definition, "clamp") {
+        popup.bind_mut().clamp = clamp;
+    }
+    if let Some(auto_size) = get_bool(definition, "auto_size") {
+        popup.bind_mut().auto_size = auto_size;
+    }
+    if let Some(anchor) = get_string(definition, "anchor") {
+        popup.bind_mut().anchor = PopupAnchor::from_name(&anchor)
+            .ok_or_else(|| format!("unknown popup anchor \"{anchor}\""))?;
+    }
+    if let Some(point) = get(definition, "point") {
+        let point = get_numbers(&point, 2).ok_or("popup \"point\" must be an array of 2 numbers")?;
+        popup.bind_mut().anchor_point = Vector2::new(point[0], point[1]);
+    }
+    if let Some(rect) = get(definition, "rect") {
+        let rect = get_numbers(&rect, 4).ok_or("popup \"rect\" must be an array of 4 numbers")?;
+        popup.bind_mut().anchor_rect = Rect2::new(Vector2::new(rect[0], rect[1]), Vector2::new(rect[2], rect[3]));
+    }
+    if let Some(group) = get(definition, "group") {
+        let Ok(group) = group.try_to::<Array<Variant>>() else {
+            return Err("popup \"group\" must be an array of target indices".to_string());
+        };
+        let mut anchor_nodes = Array::new();
+        for target_index in group.iter_shared() {
+            let target_index = target_index.try_to::<f64>().unwrap_or(-1.0);
+            let target = targets.get(target_index as usize).filter(|_| target_index >= 0.0)
+                .ok_or_else(|| format!("popup target {target_index} is out of range"))?;
+            anchor_nodes.push(&target.get_path());
+        }
+        popup.bind_mut().anchor_nodes = anchor_nodes;
+    }
+    let required_key = match popup.bind().anchor {
+        PopupAnchor::Point => Some("point"),
+        PopupAnchor::Rect => Some("rect"),
+        PopupAnchor::Group => Some("group"),
+        _ => None,
+    };
+    if let Some(required_key) = required_key.filter(|key| get(definition, key).is_none()) {
+        return Err(format!("popup anchor \"{required_key}\" requires a \"{required_key}\" key"));
+    }
+    if let Some(avoid_obstacles) = get_bool(definition, "avoid_obstacles") {
+        popup.bind_mut().avoid_obstacles = avoid_obstacles;
+    }
+    if let Some(fallback) = get(definition, "fallback") {
+        let Ok(fallback) = fallback.try_to::<Array<Variant>>() else {
+            return Err("popup \"fallback\" must be an array of positions".to_string());
+        };
+        let mut fallback_positions = PackedInt32Array::new();
+        for position in fallback.iter_shared() {
+            let position = position.try_to::<GString>().map(|position| position.to_string()).unwrap_or_default();
+            let position = PopupPosition::from_name(&position)
+                .ok_or_else(|| format!("unknown popup position \"{position}\""))?;
+            fallback_positions.push(position.to_godot() as i32);
+        }
+        popup.bind_mut().fallback_positions = fallback_positions;
+    }
+    Ok(())
+}
+
+fn build_stylebox(style: &Dictionary) -> Result<Gd<StyleBoxFlat>, String> {
+    let mut stylebox = StyleBoxFlat::new_gd();
+    stylebox.set_draw_center(false);
+    if let Some(color) = get_string(style, "bg_color") {
+        stylebox.set_bg_color(parse_color(&color)?);
+        stylebox.set_draw_center(true);
+    }
+    if let Some(color) = get_string(style, "border_color") {
+        stylebox.set_border_color(parse_color(&color)?);
+    }
+    if let Some(width) = get_number(style, "border_width") {
+        stylebox.set_border_width_all(width as i32);
+    }
+    if let Some(radius) = get_number(style, "corner_radius") {
+        stylebox.set_corner_radius_all(radius as i32);
+    }
+    if let Some(margin) = get_number(style, "expand_margin") {
+        for side in [Side::LEFT, Side::TOP, Side::RIGHT, Side::BOTTOM] {
+            stylebox.set_expand_margin(side, margin as f32);
+        }
+    }
+    Ok(stylebox)
+}
+
+fn parse_color(html: &str) -> Result<Color, String> {
+    Color::from_html(html).ok_or_else(|| format!("invalid color \"{html}\""))
+}
+
+fn get(dictionary: &Dictionary, key: &str) -> Option<Variant> {
+    dictionary.get(GString::from(key).to_variant())
+}
+
+fn get_string(dictionary: &Dictionary, key: &str) -> Option<String> {
+    get(dictionary, key)
+        .and_then(|value| value.try_to::<GString>().ok())
+        .map(|value| value.to_string())
+}
+
+fn get_number(dictionary: &Dictionary, key: &str) -> Option<f64> {
+    get(dictionary, key).and_then(|value| value.try_to::<f64>().ok())
+}
+
+fn get_bool(dictionary: &Dictionary, key: &str) -> Option<bool> {
+    get(dictionary, key).and_then(|value| value.try_to::<bool>().ok())
+}
+
+/// An array of exactly `count` numbers.
+fn get_numbers(value: &Variant, count: usize) -> Option<Vec<f32>> {
+    let array = value.try_to::<Array<Variant>>().ok()?;
+    let numbers: Vec<f32> = array.iter_shared()
+        .map(|number| number.try_to::<f64>().ok().map(|number| number as f32))
+        .collect::<Option<_>>()?;
+    (numbers.len() == count).then_some(numbers)
+}
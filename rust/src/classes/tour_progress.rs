@@ -0,0 +1,75 @@
+use godot::prelude::*;
+use godot::classes::ConfigFile;
+
+/// Tour progress stored in a ConfigFile, one section per tour id.
+pub struct TourProgress {
+    path: GString,
+    config: Gd<ConfigFile>,
+}
+
+impl TourProgress {
+    const STEP_KEY: &'static str = "step";
+    const COMPLETED_KEY: &'static str = "completed";
+    const SKIPPED_KEY: &'static str = "skipped";
+
+    /// Load progress from `path`. A missing file results in empty progress.
+    pub fn load(path: &GString) -> Self {
+        let mut config = ConfigFile::new_gd();
+        // Error is expected when the file doesn't exist yet.
+        let _ = config.load(path);
+        Self {
+            path: path.clone(),
+            config,
+        }
+    }
+
+    fn save(&mut self) {
+        let error = self.config.save(&self.path);
+        if error != godot::global::Error::OK {
+            godot_error!("Tour: couldn't save progress to \"{}\": {:?}", self.path, error);
+        }
+    }
+
+    fn get_value(&self, tour_id: &GString, key: &str, default: Variant) -> Variant {
+        self.config.get_value_ex(tour_id, key).default(&default).done()
+    }
+
+    /// Index of the last active step, -1 if the tour was never started.
+    pub fn get_step(&self, tour_id: &GString) -> i64 {
+        self.get_value(tour_id, Self::STEP_KEY, (-1i64).to_variant()).try_to::<i64>().unwrap_or(-1)
+    }
+
+    pub fn is_completed(&self, tour_id: &GString) -> bool {
+        self.get_value(tour_id, Self::COMPLETED_KEY, false.to_variant()).try_to::<bool>().unwrap_or(false)
+    }
+
+    pub fn is_skipped(&self, tour_id: &GString) -> bool {
+        self.get_value(tour_id, Self::SKIPPED_KEY, false.to_variant()).try_to::<bool>().unwrap_or(false)
+    }
+
+    /// Remember the active step of a running tour.
+    pub fn save_step(&mut self, tour_id: &GString, step: i64) {
+        self.config.set_value(tour_id, Self::STEP_KEY, &step.to_variant());
+        self.config.set_value(tour_id, Self::SKIPPED_KEY, &false.to_variant());
+        self.save();
+    }
+
+    pub fn save_completed(&mut self, tour_id: &GString) {
+        self.config.set_value(tour_id, Self::COMPLETED_KEY, &true.to_variant());
+        self.config.set_value(tour_id, Self::SKIPPED_KEY, &false.to_variant());
+        self.save();
+    }
+
+    pub fn save_skipped(&mut self, tour_id: &GString) {
+        self.config.set_value(tour_id, Self::SKIPPED_KEY, &true.to_variant());
+        self.save();
+    }
+
+    /// Forget all progress of a tour.
+    pub fn reset(&mut self, tour_id: &GString) {
+        if self.config.has_section(tour_id) {
+            self.config.erase_section(tour_id);
+            self.save();
+        }
+    }
+}
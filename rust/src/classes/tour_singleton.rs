@@ -1,10 +1,18 @@
 use godot::prelude::*;
-use godot::classes::{Object, IObject, Control, Panel};
+use godot::classes::{Object, IObject, ConfirmationDialog, Control, EditorInterface, Panel, SceneTree, Time, Window};
 use godot::classes::control::{CursorShape, LayoutPreset};
+use godot::classes::object::ConnectFlags;
 
+use super::editor_locator::EditorLocator;
 use super::focused_node::FocusedNode;
+use super::popup_container::PopupContainer;
+use super::selector::Selector;
+use super::target_rect;
 use super::theater_rect::TheaterRect;
+use super::tour_loader::{LoadedTour, TourLoader};
 use super::tour_plugin::TourPlugin;
+use super::tour_progress::TourProgress;
+use super::tour_step::TourStep;
 
 #[derive(GodotClass)]
 #[class(base=Object, rename=Tour)]
@@ -13,7 +21,42 @@ pub struct TourSingleton {
     base: Base<Object>,
     #[var]
     pub theater_rect: Gd<TheaterRect>,
+    /// Identifier of the tour.
+    #[var]
+    pub tour_id: GString,
+    /// Steps of the tour.
+    #[var]
+    pub steps: Array<Option<Gd<TourStep>>>,
+    /// Save the progress of tours with a `tour_id`.
+    #[var]
+    pub save_progress: bool,
+    /// ConfigFile the progress is saved to.
+    #[var]
+    pub progress_path: GString,
+    /// Shake the popup of the active step when a click outside of the focused nodes is blocked.
+    #[var]
+    pub shake_popup_on_blocked_click: bool,
+    /// Offer to skip the tour after this many blocked clicks during a step. Disabled when 0.
+    #[var]
+    pub blocked_clicks_before_help: i64,
     pub tour_plugin: Option<Gd<TourPlugin>>,
+    // TheaterRects following theater_rect in other windows.
+    window_theater_rects: Vec<Gd<TheaterRect>>,
+    // Overlays & popups created by `load_tour`.
+    loaded_nodes: Vec<Gd<Node>>,
+    // Index of the active step, -1 when the tour isn't running.
+    current_step: i64,
+    // Node, signal & callable connected for the active step's `advance_signal`.
+    advance_connection: Option<(Gd<Node>, StringName, Callable)>,
+    // Focused node whose target `advance_signal` is connected to, when the active step has no `advance_node`.
+    advance_focused_node: Option<Gd<FocusedNode>>,
+    // Tree `on_step_process_frame` is connected to.
+    process_frame_tree: Option<Gd<SceneTree>>,
+    // Time the active step started at, in milliseconds.
+    step_started_msec: u64,
+    // Clicks blocked by the theater_rect during the active step.
+    blocked_clicks: i64,
+    help_dialog: Option<Gd<ConfirmationDialog>>,
 }
 
 #[godot_api]
@@ -25,18 +68,49 @@ impl IObject for TourSingleton {
         Self {
             base,
             theater_rect,
-            tour_plugin: None
+            tour_id: GString::new(),
+            steps: Array::new(),
+            save_progress: true,
+            progress_path: GString::from("user://gdtour_progress.cfg"),
+            shake_popup_on_blocked_click: true,
+            blocked_clicks_before_help: 5,
+            tour_plugin: None,
+            window_theater_rects: Vec::new(),
+            loaded_nodes: Vec::new(),
+            current_step: -1,
+            advance_connection: None,
+            advance_focused_node: None,
+            process_frame_tree: None,
+            step_started_msec: 0,
+            blocked_clicks: 0,
+            help_dialog: None,
         }
     }
 }
 
 #[godot_api]
 impl TourSingleton {
+    /// Emitted after the tour moved to another step.
+    #[signal]
+    fn step_changed(index: i64, step: Gd<TourStep>);
+
+    /// Emitted when the tour was completed.
+    #[signal]
+    fn tour_finished();
+
+    /// Emitted when the tour was stopped before its last step.
+    #[signal]
+    fn tour_cancelled(index: i64);
+
+    /// Emitted when the user is offered to skip the tour after `blocked_clicks_before_help` blocked clicks.
+    #[signal]
+    fn help_offered(index: i64);
+
     // region: Focused Nodes
 
     /// Helper function for creating a focused node resource.
     #[func]
-    fn create_focused_node(target: Option<Gd<Control>>, overlay: Option<Gd<Panel>>) -> Gd<FocusedNode> {
+    pub fn create_focused_node(target: Option<Gd<Node>>, overlay: Option<Gd<Panel>>) -> Gd<FocusedNode> {
         let mut focused_node = FocusedNode::new_gd();
         if let Some(target) = target {
             focused_node.bind_mut().target = target.get_path();
@@ -47,10 +121,29 @@ impl TourSingleton {
         focused_node
     }
 
+    /// Helper function for creating a focused node resource with a selector target.
+    /// The target is queried again whenever it's freed or leaves the tree.
+    #[func]
+    pub fn create_focused_node_by_selector(selector: GString, overlay: Option<Gd<Panel>>) -> Gd<FocusedNode> {
+        let mut focused_node = Self::create_focused_node(None, overlay);
+        focused_node.bind_mut().selector = selector;
+        focused_node
+    }
+
+    /// Helper function for creating a focused node resource with a resolver returning the target.
+    /// The resolver is called again whenever the target is freed or leaves the tree.
+    #[func]
+    pub fn create_focused_node_by_resolver(resolver: Callable, overlay: Option<Gd<Panel>>) -> Gd<FocusedNode> {
+        let mut focused_node = Self::create_focused_node(None, overlay);
+        focused_node.bind_mut().resolver = resolver;
+        focused_node
+    }
+
     /// Helper function for adding a focused node to the theater_rect.
     #[func]
     fn add_focused_node(&mut self, focused_node: Gd<FocusedNode>) {
         self.theater_rect.bind_mut().focused_nodes.push(Some(&focused_node));
+        self.add_window_theater_rects();
     }
 
     /// Helper function for removing a focused node from the theater_rect.
@@ -68,6 +161,548 @@ impl TourSingleton {
 
     // endregion: Focused Nodes
 
+    // region: Windows
+
+    /// Get the TheaterRect covering `window`. Returns the theater_rect for its own window,
+    /// otherwise a TheaterRect following the theater_rect is created in `window`.
+    /// Overlays of targets in `window` should be added as children of the returned TheaterRect.
+    #[func]
+    pub fn get_theater_rect_for_window(&mut self, mut window: Gd<Window>) -> Gd<TheaterRect> {
+        if target_rect::get_native_window(&self.theater_rect.clone().upcast()).as_ref() == Some(&window) {
+            return self.theater_rect.clone();
+        }
+        self.window_theater_rects.retain(|theater_rect| theater_rect.is_instance_valid());
+        let existing = self.window_theater_rects.iter().find(|theater_rect| {
+            target_rect::get_native_window(&(*theater_rect).clone().upcast()).as_ref() == Some(&window)
+        });
+        if let Some(theater_rect) = existing {
+            return theater_rect.clone();
+        }
+
+        let mut theater_rect = TheaterRect::new_alloc();
+        theater_rect.set_anchors_preset(LayoutPreset::FULL_RECT);
+        theater_rect.set_default_cursor_shape(CursorShape::FORBIDDEN);
+        if let Some(theme) = self.theater_rect.get_theme() {
+            theater_rect.set_theme(&theme);
+        }
+        theater_rect.bind_mut().follow = Some(self.theater_rect.clone());
+        window.add_child(&theater_rect);
+        self.window_theater_rects.push(theater_rect.clone());
+        theater_rect
+    }
+
+    /// Create TheaterRects for focused nodes in other windows than the theater_rect.
+    fn add_window_theater_rects(&mut self) {
+        let windows: Vec<Gd<Window>> = {
+            let theater_rect = self.theater_rect.bind();
+            theater_rect.focused_nodes.iter_shared()
+                .flatten()
+                .filter_map(|focused_node| theater_rect.get_target(&focused_node))
+                .filter_map(|target| target_rect::get_native_window(&target))
+                .collect()
+        };
+        for window in windows {
+            self.get_theater_rect_for_window(window);
+        }
+    }
+
+    // endregion: Windows
+
+    // region: Steps
+
+    /// Helper function for creating a tour step resource.
+    #[func]
+    fn create_tour_step(focused_nodes: Array<Option<Gd<FocusedNode>>>, popup: Option<Gd<Control>>) -> Gd<TourStep> {
+        let mut tour_step = TourStep::new_gd();
+        tour_step.bind_mut().focused_nodes = focused_nodes;
+        if let Some(popup) = popup {
+            tour_step.bind_mut().popup = popup.get_path();
+        }
+        tour_step
+    }
+
+    /// Helper function for appending a step to the tour.
+    #[func]
+    fn add_step(&mut self, step: Gd<TourStep>) {
+        self.steps.push(Some(&step));
+    }
+
+    /// Helper function for removing all steps from the tour.
+    #[func]
+    fn clear_steps(&mut self) {
+        if self.is_running() {
+            self.cancel();
+        }
+        self.steps.clear();
+    }
+
+    /// Index of the active step, -1 when the tour isn't running.
+    #[func]
+    pub fn get_current_step(&self) -> i64 {
+        self.current_step
+    }
+
+    /// Whether a step of the tour is currently active.
+    #[func]
+    pub fn is_running(&self) -> bool {
+        self.current_step >= 0
+    }
+
+    /// Start the tour from the first step.
+    #[func]
+    pub fn start(&mut self) -> bool {
+        self.go_to(0)
+    }
+
+    /// Move to the next step, finishing the tour after the last one.
+    #[func]
+    pub fn next(&mut self) {
+        if !self.is_running() {
+            return;
+        }
+        if self.current_step + 1 >= self.steps.len() as i64 {
+            self.finish();
+        } else {
+            self.go_to(self.current_step + 1);
+        }
+    }
+
+    /// Move to the previous step.
+    #[func]
+    pub fn previous(&mut self) {
+        if self.current_step > 0 {
+            self.go_to(self.current_step - 1);
+        }
+    }
+
+    /// Move to the step at `index`. Returns false if there is no such step.
+    #[func]
+    pub fn go_to(&mut self, index: i64) -> bool {
+        let Some(step) = self.get_step(index) else {
+            godot_error!("Tour: no step at index {index}.");
+            return false;
+        };
+        self.current_step = index;
+        self.blocked_clicks = 0;
+        self.apply_step(&step);
+        if let Some(mut progress) = self.get_progress() {
+            progress.save_step(&self.tour_id, index);
+        }
+        self.base_mut().emit_signal("step_changed", &[index.to_variant(), step.to_variant()]);
+        true
+    }
+
+    /// Complete the tour and hide the theater_rect.
+    #[func]
+    pub fn finish(&mut self) {
+        if !self.is_running() {
+            return;
+        }
+        self.stop();
+        if let Some(mut progress) = self.get_progress() {
+            progress.save_completed(&self.tour_id);
+        }
+        self.base_mut().emit_signal("tour_finished", &[]);
+    }
+
+    /// Stop the tour before it was completed and hide the theater_rect.
+    #[func]
+    pub fn cancel(&mut self) {
+        if !self.is_running() {
+            return;
+        }
+        let index = self.current_step;
+        self.stop();
+        if let Some(mut progress) = self.get_progress() {
+            progress.save_skipped(&self.tour_id);
+        }
+        self.base_mut().emit_signal("tour_cancelled", &[index.to_variant()]);
+    }
+
+    fn get_step(&self, index: i64) -> Option<Gd<TourStep>> {
+        if index < 0 {
+            return None;
+        }
+        self.steps.get(index as usize).flatten()
+    }
+
+    fn apply_step(&mut self, step: &Gd<TourStep>) {
+        // Copy, so removing focused nodes doesn't modify the step.
+        let (focused_nodes, allowed_actions, allowed_shortcuts) = {
+            let step = step.bind();
+            (step.focused_nodes.duplicate_shallow(), step.allowed_actions.clone(), step.allowed_shortcuts.clone())
+        };
+        {
+            let mut theater_rect = self.theater_rect.bind_mut();
+            theater_rect.focused_nodes = focused_nodes;
+            theater_rect.allowed_actions = allowed_actions;
+            theater_rect.allowed_shortcuts = allowed_shortcuts;
+        }
+        self.add_window_theater_rects();
+        self.update_popups(Some(step));
+        self.connect_blocked_click();
+        self.theater_rect.bind_mut().show_animated();
+        self.disconnect_advance_conditions();
+        self.connect_advance_conditions(step);
+    }
+
+    fn stop(&mut self) {
+        self.current_step = -1;
+        self.blocked_clicks = 0;
+        if let Some(mut help_dialog) = self.help_dialog.clone() {
+            help_dialog.hide();
+        }
+        self.disconnect_advance_conditions();
+        {
+            let mut theater_rect = self.theater_rect.bind_mut();
+            theater_rect.allowed_actions = Array::new();
+            theater_rect.allowed_shortcuts = Array::new();
+        }
+        self.update_popups(None);
+        let fades_out = self.theater_rect.is_visible() && self.theater_rect.bind().fade_duration > 0.0;
+        if fades_out {
+            // Keep the cutouts while the theater_rect fades out.
+            self.connect_theater_rect_hidden();
+        } else {
+            // Without fading `hidden` is emitted immediately, while this is still borrowed.
+            self.disconnect_theater_rect_hidden();
+        }
+        self.theater_rect.bind_mut().hide_animated();
+        if !fades_out {
+            self.clear_focused_nodes();
+        }
+    }
+
+    /// Show the popup of the active step and hide the popups of all other steps.
+    fn update_popups(&self, active_step: Option<&Gd<TourStep>>) {
+        let active_popup = active_step.map(|step| step.bind().popup.clone());
+        self.steps.iter_shared().flatten().for_each(|step| {
+            let popup_nodepath = step.bind().popup.clone();
+            if popup_nodepath.is_empty() {
+                return;
+            }
+            if let Some(mut popup) = self.theater_rect.try_get_node_as::<Control>(&popup_nodepath) {
+                popup.set_visible(Some(&popup_nodepath) == active_popup.as_ref());
+            }
+        });
+    }
+
+    // endregion: Steps
+
+    // region: Advance conditions
+
+    /// Called when the active step's `advance_signal` is emitted.
+    #[func]
+    fn on_step_advance_signal(&mut self) {
+        self.next();
+    }
+
+    /// Called when the theater_rect found or replaced the target of a focused node.
+    /// Moves the `advance_signal` connection to the new target of `advance_focused_node`.
+    #[func]
+    fn on_target_resolved(&mut self, focused_node: Gd<FocusedNode>, target: Gd<Node>) {
+        if self.advance_focused_node.as_ref() != Some(&focused_node) {
+            return;
+        }
+        if self.advance_connection.as_ref().is_some_and(|(node, _, _)| *node == target) {
+            return;
+        }
+        let Some(step) = self.get_step(self.current_step) else {
+            return;
+        };
+        let signal = step.bind().advance_signal.clone();
+        self.disconnect_advance_signal();
+        self.connect_advance_signal(target, signal);
+    }
+
+    /// Called every frame while the active step has an `advance_predicate` or `advance_timeout`.
+    #[func]
+    fn on_step_process_frame(&mut self) {
+        let Some(step) = self.get_step(self.current_step) else {
+            return;
+        };
+        let (predicate, timeout) = {
+            let step = step.bind();
+            (step.advance_predicate.clone(), step.advance_timeout)
+        };
+        let elapsed = (Time::singleton().get_ticks_msec() - self.step_started_msec) as f64 / 1000.0;
+        let timed_out = timeout > 0.0 && elapsed >= timeout;
+        let predicate_met = predicate.is_valid() && {
+            // Allow the predicate to call back into the singleton.
+            let _guard = self.base_mut();
+            predicate.call(&[]).booleanize()
+        };
+        if timed_out || predicate_met {
+            self.next();
+        }
+    }
+
+    fn connect_advance_conditions(&mut self, step: &Gd<TourStep>) {
+        let (signal, node_path, timeout, predicate) = {
+            let step = step.bind();
+            (step.advance_signal.clone(), step.advance_node.clone(), step.advance_timeout, step.advance_predicate.clone())
+        };
+
+        if !signal.is_empty() {
+            let first_focused_node = step.bind().focused_nodes.iter_shared().flatten().next();
+            if !node_path.is_empty() {
+                match self.theater_rect.try_get_node_as::<Node>(&node_path) {
+                    Some(node) => self.connect_advance_signal(node, signal),
+                    None => godot_warn!("Tour: no node at \"{}\" to advance on \"{}\".", node_path, signal),
+                }
+            } else if let Some(focused_node) = first_focused_node {
+                // Reconnected in `on_target_resolved` when the target is found or replaced.
+                let target = self.theater_rect.bind().get_target(&focused_node);
+                self.advance_focused_node = Some(focused_node);
+                self.connect_target_resolved();
+                if let Some(target) = target {
+                    self.connect_advance_signal(target, signal);
+                }
+            } else {
+                godot_warn!("Tour: no node to advance on \"{}\".", signal);
+            }
+        }
+
+        if timeout > 0.0 || predicate.is_valid() {
+            if let Some(mut tree) = self.theater_rect.get_tree() {
+                tree.connect("process_frame", &self.to_gd().callable("on_step_process_frame"));
+                self.process_frame_tree = Some(tree);
+            }
+        }
+        self.step_started_msec = Time::singleton().get_ticks_msec();
+    }
+
+    fn connect_advance_signal(&mut self, mut node: Gd<Node>, signal: StringName) {
+        if !node.has_signal(&signal) {
+            godot_warn!("Tour: {} has no signal \"{}\".", node.get_class(), signal);
+            return;
+        }
+        let arg_count = Self::get_signal_arg_count(&node, &signal);
+        // Drop the signal's arguments, e.g. the text of `text_changed`.
+        let callable = self.to_gd().callable("on_step_advance_signal").unbind(arg_count);
+        node.connect(&signal, &callable);
+        self.advance_connection = Some((node, signal, callable));
+    }
+
+    fn disconnect_advance_signal(&mut self) {
+        if let Some((mut node, signal, callable)) = self.advance_connection.take() {
+            if node.is_instance_valid() && node.is_connected(&signal, &callable) {
+                node.disconnect(&signal, &callable);
+            }
+        }
+    }
+
+    fn connect_target_resolved(&mut self) {
+        let callable = self.to_gd().callable("on_target_resolved");
+        if !self.theater_rect.is_connected("target_resolved", &callable) {
+            self.theater_rect.connect("target_resolved", &callable);
+        }
+    }
+
+    fn disconnect_advance_conditions(&mut self) {
+        self.disconnect_advance_signal();
+        self.advance_focused_node = None;
+        if let Some(mut tree) = self.process_frame_tree.take() {
+            let callable = self.to_gd().callable("on_step_process_frame");
+            if tree.is_connected("process_frame", &callable) {
+                tree.disconnect("process_frame", &callable);
+            }
+        }
+    }
+
+    fn get_signal_arg_count(node: &Gd<Node>, signal: &StringName) -> usize {
+        let name_key = GString::from("name").to_variant();
+        let args_key = GString::from("args").to_variant();
+        node.get_signal_list().iter_shared()
+            .find(|signal_info| signal_info.get(name_key.clone()).map(|name| name.to_string()) == Some(signal.to_string()))
+            .and_then(|signal_info| signal_info.get(args_key.clone()))
+            .and_then(|args| args.try_to::<Array<Variant>>().ok())
+            .map(|args| args.len())
+            .unwrap_or(0)
+    }
+
+    // endregion: Advance conditions
+
+    // region: Blocked clicks
+
+    /// Number of clicks blocked by the theater_rect during the active step.
+    #[func]
+    pub fn get_blocked_click_count(&self) -> i64 {
+        self.blocked_clicks
+    }
+
+    /// Called when the theater_rect blocked a click outside of the focused nodes.
+    #[func]
+    fn on_blocked_click(&mut self, _position: Vector2) {
+        if !self.is_running() {
+            return;
+        }
+        if self.shake_popup_on_blocked_click {
+            if let Some(mut popup) = self.get_current_popup() {
+                popup.bind_mut().shake();
+            }
+        }
+        self.blocked_clicks += 1;
+        if self.blocked_clicks_before_help > 0 && self.blocked_clicks == self.blocked_clicks_before_help {
+            self.offer_help();
+        }
+    }
+
+    /// Called when the theater_rect finished fading out.
+    #[func]
+    fn on_theater_rect_hidden(&mut self) {
+        if !self.is_running() {
+            self.clear_focused_nodes();
+        }
+    }
+
+    fn connect_theater_rect_hidden(&mut self) {
+        let callable = self.to_gd().callable("on_theater_rect_hidden");
+        if !self.theater_rect.is_connected("hidden", &callable) {
+            self.theater_rect.connect_ex("hidden", &callable)
+                .flags(ConnectFlags::ONE_SHOT.ord() as u32)
+                .done();
+        }
+    }
+
+    fn disconnect_theater_rect_hidden(&mut self) {
+        let callable = self.to_gd().callable("on_theater_rect_hidden");
+        if self.theater_rect.is_connected("hidden", &callable) {
+            self.theater_rect.disconnect("hidden", &callable);
+        }
+    }
+
+    fn connect_blocked_click(&mut self) {
+        let callable = self.to_gd().callable("on_blocked_click");
+        if !self.theater_rect.is_connected("blocked_click", &callable) {
+            self.theater_rect.connect("blocked_click", &callable);
+        }
+    }
+
+    fn get_current_popup(&self) -> Option<Gd<PopupContainer>> {
+        let popup_nodepath = self.get_step(self.current_step)?.bind().popup.clone();
+        if popup_nodepath.is_empty() {
+            return None;
+        }
+        self.theater_rect.try_get_node_as::<PopupContainer>(&popup_nodepath)
+    }
+
+    /// Ask the user whether to skip the tour.
+    fn offer_help(&mut self) {
+        let index = self.current_step;
+        self.base_mut().emit_signal("help_offered", &[index.to_variant()]);
+        if !self.theater_rect.is_inside_tree() {
+            return;
+        }
+        let mut help_dialog = match self.help_dialog.clone().filter(|help_dialog| help_dialog.is_instance_valid()) {
+            Some(help_dialog) => help_dialog,
+            None => {
+                let mut help_dialog = ConfirmationDialog::new_alloc();
+                help_dialog.set_title("Need help?");
+                help_dialog.set_text("Only the highlighted area can be used right now.\nFollow the instructions to continue, or skip the tour.");
+                help_dialog.set_ok_button_text("Skip Tour");
+                help_dialog.set_cancel_button_text("Continue Tour");
+                help_dialog.connect("confirmed", &self.to_gd().callable("cancel"));
+                self.theater_rect.add_child(&help_dialog);
+                self.help_dialog = Some(help_dialog.clone());
+                help_dialog
+            }
+        };
+        help_dialog.popup_centered();
+    }
+
+    // endregion: Blocked clicks
+
+    // region: Progress
+
+    /// Continue the tour from the step saved in a previous session, or start it.
+    /// Returns false if the tour was already completed.
+    #[func]
+    pub fn resume(&mut self) -> bool {
+        if self.has_completed_tour(self.tour_id.clone()) {
+            return false;
+        }
+        let saved_step = self.get_saved_step(self.tour_id.clone());
+        if saved_step >= 0 && saved_step < self.steps.len() as i64 {
+            return self.go_to(saved_step);
+        }
+        self.start()
+    }
+
+    /// Forget the saved progress of a tour.
+    #[func]
+    pub fn reset_progress(&mut self, tour_id: GString) {
+        TourProgress::load(&self.progress_path).reset(&tour_id);
+    }
+
+    /// Whether the user reached the end of a tour.
+    #[func]
+    pub fn has_completed_tour(&self, tour_id: GString) -> bool {
+        TourProgress::load(&self.progress_path).is_completed(&tour_id)
+    }
+
+    /// Whether the user cancelled a tour before completing it.
+    #[func]
+    pub fn has_skipped_tour(&self, tour_id: GString) -> bool {
+        TourProgress::load(&self.progress_path).is_skipped(&tour_id)
+    }
+
+    /// Index of the last active step of a tour, -1 if it was never started.
+    #[func]
+    pub fn get_saved_step(&self, tour_id: GString) -> i64 {
+        TourProgress::load(&self.progress_path).get_step(&tour_id)
+    }
+
+    fn get_progress(&self) -> Option<TourProgress> {
+        if !self.save_progress || self.tour_id.is_empty() {
+            return None;
+        }
+        Some(TourProgress::load(&self.progress_path))
+    }
+
+    // endregion: Progress
+
+    // region: Tour definitions
+
+    /// Load a tour definition file, replacing the current steps.
+    /// Overlays & popups are created as children of the theater_rect, overlays of targets in other windows as
+    /// children of the TheaterRect covering that window.
+    #[func]
+    pub fn load_tour(&mut self, path: GString) -> bool {
+        let result = TourLoader::new(self).load_file(&path.to_string());
+        self.apply_loaded_tour(result, &path.to_string())
+    }
+
+    /// Load a tour definition from a dictionary, replacing the current steps.
+    #[func]
+    pub fn load_tour_from_dictionary(&mut self, definition: Dictionary) -> bool {
+        let result = TourLoader::new(self).load_dictionary(&definition);
+        self.apply_loaded_tour(result, "dictionary")
+    }
+
+    fn apply_loaded_tour(&mut self, result: Result<LoadedTour, String>, source: &str) -> bool {
+        match result {
+            Ok(loaded_tour) => {
+                self.clear_steps();
+                for mut node in self.loaded_nodes.drain(..) {
+                    if node.is_instance_valid() {
+                        node.queue_free();
+                    }
+                }
+                self.tour_id = loaded_tour.id;
+                self.steps = loaded_tour.steps;
+                self.loaded_nodes = loaded_tour.nodes;
+                true
+            }
+            Err(error) => {
+                godot_error!("Tour: couldn't load tour from {source}: {error}");
+                false
+            }
+        }
+    }
+
+    // endregion: Tour definitions
+
     // region: TourPlugin function binds
 
     /// Get base control of editor window.
@@ -101,23 +736,13 @@ impl TourSingleton {
 
     // region: Editor Control helper functions.
 
-    /// Get run bar control in title bar.
+    /// Get run bar control in title bar. Requires Godot 4.2+.
     #[func]
     pub fn get_run_bar(title_bar: Gd<Control>) -> Option<Gd<Control>> {
-        let title_bar_children = title_bar.find_children_ex("*EditorRunBar*")
-            .type_("EditorRunBar")
-            .recursive(false)
-            .owned(false)
-            .done();
-        if let Some(node) = title_bar_children.get(0) {
-            if let Ok(run_bar) = node.try_cast::<Control>() {
-                return Some(run_bar);
-            }
-        }
-        None
+        EditorLocator::RUN_BAR.locate(&title_bar)
     }
 
-    /// Get run bar control in title bar.
+    /// Get run bar control in title bar. Requires Godot 4.2+.
     #[func]
     pub fn get_run_bar_full(&self) -> Option<Gd<Control>> {
         if let Some(title_bar) = self.get_title_bar_full() {
@@ -144,20 +769,148 @@ impl TourSingleton {
         None
     }
 
+    /// Get scene tree dock in main control.
     #[func]
     pub fn get_scene_tree_dock(&self, main: Gd<Control>) -> Option<Gd<Control>> {
-        let main_children = main.find_children_ex("Scene")
-            .type_("SceneTreeDock")
-            .recursive(true)
-            .owned(false)
-            .done();
-        if let Some(node) = main_children.get(0) {
-            if let Ok(control) = node.try_cast::<Control>() {
-                return Some(control);
+        EditorLocator::SCENE_TREE_DOCK.locate(&main)
+    }
+
+    /// Get the inspector dock.
+    #[func]
+    pub fn get_inspector(&self) -> Option<Gd<Control>> {
+        self.locate(&EditorLocator::INSPECTOR)
+    }
+
+    /// Get the dock listing signals & groups.
+    #[func]
+    pub fn get_node_dock(&self) -> Option<Gd<Control>> {
+        self.locate(&EditorLocator::NODE_DOCK)
+    }
+
+    /// Get the dock browsing the project files.
+    #[func]
+    pub fn get_filesystem_dock(&self) -> Option<Gd<Control>> {
+        self.locate(&EditorLocator::FILESYSTEM_DOCK)
+    }
+
+    /// Get the dock with the import settings of the selected file.
+    #[func]
+    pub fn get_import_dock(&self) -> Option<Gd<Control>> {
+        self.locate(&EditorLocator::IMPORT_DOCK)
+    }
+
+    /// Get the dock listing the undo/redo history.
+    #[func]
+    pub fn get_history_dock(&self) -> Option<Gd<Control>> {
+        self.locate(&EditorLocator::HISTORY_DOCK)
+    }
+
+    /// Get the Output bottom panel.
+    #[func]
+    pub fn get_output_panel(&self) -> Option<Gd<Control>> {
+        self.locate(&EditorLocator::OUTPUT_PANEL)
+    }
+
+    /// Get the Debugger bottom panel.
+    #[func]
+    pub fn get_debugger_panel(&self) -> Option<Gd<Control>> {
+        self.locate(&EditorLocator::DEBUGGER_PANEL)
+    }
+
+    /// Get the Animation bottom panel.
+    #[func]
+    pub fn get_animation_panel(&self) -> Option<Gd<Control>> {
+        self.locate(&EditorLocator::ANIMATION_PANEL)
+    }
+
+    /// Get the Shader Editor bottom panel.
+    #[func]
+    pub fn get_shader_editor_panel(&self) -> Option<Gd<Control>> {
+        self.locate(&EditorLocator::SHADER_EDITOR_PANEL)
+    }
+
+    /// Get the container of the main screen buttons (2D, 3D, Script, AssetLib).
+    #[func]
+    pub fn get_main_screen_buttons(&self) -> Option<Gd<Control>> {
+        self.locate(&EditorLocator::MAIN_SCREEN_BUTTONS)
+    }
+
+    /// Get a main screen button by name, e.g. `2D`, `3D`, `Script` or `AssetLib`.
+    #[func]
+    pub fn get_main_screen_button(&self, name: GString) -> Option<Gd<Control>> {
+        let main_screen_buttons = self.get_main_screen_buttons()?;
+        let button = main_screen_buttons.find_child_ex(&name).recursive(false).owned(false).done();
+        if button.is_none() {
+            godot_warn!("Tour: couldn't find main screen button \"{name}\".");
+        }
+        button?.try_cast::<Control>().ok()
+    }
+
+    /// Get the tabs of the open scenes.
+    #[func]
+    pub fn get_scene_tabs(&self) -> Option<Gd<Control>> {
+        self.locate(&EditorLocator::SCENE_TABS)
+    }
+
+    /// Get the main menu bar.
+    #[func]
+    pub fn get_menu_bar(&self) -> Option<Gd<Control>> {
+        self.locate(&EditorLocator::MENU_BAR)
+    }
+
+    /// Get an editor control by name. Used by tour definition files.
+    /// Available names: `base_control`, `main_screen` & the names of `EditorLocator::ALL`, e.g. `title_bar`,
+    /// `run_bar`, `scene_tree_dock`, `inspector`, `filesystem_dock`, `output_panel` or `menu_bar`.
+    #[func]
+    pub fn get_editor_control(&self, name: GString) -> Option<Gd<Control>> {
+        match name.to_string().as_str() {
+            "base_control" => self.get_base_control(),
+            "main_screen" => {
+                // EditorInterface is only available while the plugin is active.
+                self.tour_plugin.as_ref()?;
+                EditorInterface::singleton().get_editor_main_screen().map(|main_screen| main_screen.upcast())
+            }
+            name => {
+                let Some(locator) = EditorLocator::get(name) else {
+                    godot_warn!("Tour: unknown editor control \"{name}\".");
+                    return None;
+                };
+                self.locate(locator)
             }
         }
-        None
+    }
+
+    fn locate(&self, locator: &EditorLocator) -> Option<Gd<Control>> {
+        locator.locate(&self.get_base_control()?)
     }
 
     // endregion: Editor Control helper functions.
+
+    // region: Selectors
+
+    /// First node below `root` matching `selector`, e.g. `SceneTreeDock > SceneTreeEditor Tree`.
+    #[func]
+    pub fn query(&self, selector: GString, root: Gd<Node>) -> Option<Gd<Node>> {
+        match Selector::parse(&selector.to_string()) {
+            Ok(selector) => selector.query(&root),
+            Err(error) => {
+                godot_error!("Tour: invalid selector: {error}");
+                None
+            }
+        }
+    }
+
+    /// All nodes below `root` matching `selector`, in tree order.
+    #[func]
+    pub fn query_all(&self, selector: GString, root: Gd<Node>) -> Array<Gd<Node>> {
+        match Selector::parse(&selector.to_string()) {
+            Ok(selector) => selector.query_all(&root).into_iter().collect(),
+            Err(error) => {
+                godot_error!("Tour: invalid selector: {error}");
+                Array::new()
+            }
+        }
+    }
+
+    // endregion: Selectors
 }
@@ -0,0 +1,52 @@
+use godot::prelude::*;
+use godot::builtin::NodePath;
+use godot::classes::{Resource, Shortcut};
+
+use super::focused_node::FocusedNode;
+
+#[derive(GodotClass)]
+#[class(tool, init, base=Resource)]
+/// A single step of a tour.
+pub struct TourStep {
+    base: Base<Resource>,
+    /// Nodes to focus on while this step is active.
+    #[export]
+    pub focused_nodes: Array<Option<Gd<FocusedNode>>>,
+    /// Popup shown while this step is active. Resolved relative to the theater_rect.
+    #[export]
+    #[var(hint = NODE_PATH_VALID_TYPES, hint_string = "Control")]
+    pub popup: NodePath,
+    /// Title of the step.
+    #[export]
+    pub title: GString,
+    /// Description of the step.
+    #[export(multiline)]
+    pub text: GString,
+    /// Extra data for scripts reacting to the step.
+    #[export]
+    pub metadata: Dictionary,
+    /// Advance to the next step when `advance_node` emits this signal, e.g. `pressed`.
+    #[export_group(name = "Advance")]
+    #[export]
+    pub advance_signal: StringName,
+    /// Node emitting `advance_signal`. Defaults to the target of the first focused node.
+    #[export]
+    pub advance_node: NodePath,
+    /// Advance to the next step after this many seconds. Disabled when 0.
+    #[export(range = (0.0, 3600.0, or_greater, suffix = "s"))]
+    pub advance_timeout: f64,
+    /// Actions allowed while the theater_rect confines input.
+    #[export_group(name = "Input")]
+    #[export]
+    pub allowed_actions: Array<StringName>,
+    /// Shortcuts allowed while the theater_rect confines input.
+    #[export]
+    pub allowed_shortcuts: Array<Option<Gd<Shortcut>>>,
+    /// Advance to the next step once this returns true. Called every frame.
+    #[var]
+    #[init(val = Callable::invalid())]
+    pub advance_predicate: Callable,
+}
+
+#[godot_api]
+impl TourStep {}